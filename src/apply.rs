@@ -0,0 +1,671 @@
+use crate::cli::ApplyArgs;
+use crate::clipboard::paste_from_clipboard;
+use crate::utils::separator;
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+
+const DIFF_CONTEXT: usize = 3;
+/// Above this many line pairs the diff falls back to replacing the whole file
+const MAX_DIFF_CELLS: usize = 25_000_000;
+/// Symlinks followed while resolving one path before it is taken for a loop
+const MAX_SYMLINK_HOPS: usize = 40;
+const EXTERNAL_IMPORTS_HEADER: &str = "External imports (not included)";
+
+static FILE_HEADER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^\*\*([^*\n]+?):\*\*(?: \[([^\]\n]*)\])?[ \t]*\r?\n").unwrap()
+});
+static XML_DOCUMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?s)<(document|file)\b([^>]*)>(.*?)</(?:document|file)>"#).unwrap()
+});
+static XML_PATH_ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\b(?:path|source)\s*=\s*"([^"]+)""#).unwrap());
+static XML_SOURCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<source>\s*(.*?)\s*</source>").unwrap());
+static XML_CONTENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<(?:document_content|content)>(.*?)</(?:document_content|content)>").unwrap()
+});
+static FENCE_INFO_PATH: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?:\b(?:path|file|title)=["']?([^"'\s]+)|^\w+:([^\s]+)$|^([^\s]*[./][^\s]*)$)"#)
+        .unwrap()
+});
+static PATH_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:#+\s*)?(?:\*\*|`)?([\w./-]*[./][\w./-]*?)(?:`|\*\*)?:?(?:\*\*)?\s*$").unwrap()
+});
+
+/// A file found in a pasted response
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParsedFile {
+    pub path: String,
+    pub content: String,
+}
+
+/// The body under a `**path:**` header. A fence right after the header is the whole body;
+/// otherwise the body runs to a separator line or the next header.
+fn header_body(section: &str) -> String {
+    let lines: Vec<&str> = section.split_inclusive('\n').collect();
+    if let Some(first) = lines.iter().position(|line| !line.trim().is_empty())
+        && lines[first].trim_start().starts_with("```")
+        && let Some(close) = (first + 1..lines.len()).find(|&k| lines[k].trim_end() == "```")
+    {
+        return lines[first + 1..close].concat();
+    }
+
+    let separator = separator();
+    let end = lines
+        .iter()
+        .position(|line| line.trim_end() == separator)
+        .unwrap_or(lines.len());
+
+    let body = lines[..end].concat();
+    // Each file is followed by a blank line
+    match body.strip_suffix("\n\n") {
+        Some(stripped) => format!("{}\n", stripped),
+        None => body,
+    }
+}
+
+/// Whether a header note marks a placeholder for a file whose content was left out: omitted
+/// for its size, unreadable, or a symlink that was not followed.
+fn is_placeholder_note(note: &str) -> bool {
+    note.split("; ").any(|part| {
+        part.starts_with("omitted:") || part.starts_with("error:") || part.starts_with("symlink ->")
+    })
+}
+
+/// Parses pacont's default `**path:**` format, as joined with separator lines.
+fn parse_headers(text: &str) -> Vec<ParsedFile> {
+    let headers: Vec<_> = FILE_HEADER.captures_iter(text).collect();
+    let mut files = Vec::new();
+
+    for (index, caps) in headers.iter().enumerate() {
+        let start = caps.get(0).unwrap().end();
+        let end = headers
+            .get(index + 1)
+            .map_or(text.len(), |next| next.get(0).unwrap().start());
+        let path = caps[1].trim();
+        if path == EXTERNAL_IMPORTS_HEADER
+            || caps
+                .get(2)
+                .is_some_and(|note| is_placeholder_note(note.as_str()))
+        {
+            continue;
+        }
+
+        files.push(ParsedFile {
+            path: path.to_string(),
+            content: header_body(&text[start..end]),
+        });
+    }
+
+    files
+}
+
+/// Parses `<document path="...">` / `<file path="...">` elements, or documents with `<source>`
+/// and `<document_content>` children.
+fn parse_xml(text: &str) -> Vec<ParsedFile> {
+    XML_DOCUMENT
+        .captures_iter(text)
+        .filter_map(|caps| {
+            let attributes = &caps[2];
+            let inner = &caps[3];
+            let (path, content) = match XML_PATH_ATTRIBUTE.captures(attributes) {
+                Some(path) => (path[1].to_string(), inner),
+                None => {
+                    let source = XML_SOURCE.captures(inner)?;
+                    let content = XML_CONTENT.captures(inner)?;
+                    (source[1].to_string(), content.get(1)?.as_str())
+                }
+            };
+            let content = content.strip_prefix('\n').unwrap_or(content);
+            Some(ParsedFile {
+                path,
+                content: content.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn fence_path(info: &str, previous_line: Option<&str>) -> Option<String> {
+    if let Some(caps) = FENCE_INFO_PATH.captures(info.trim()) {
+        return caps
+            .iter()
+            .skip(1)
+            .flatten()
+            .next()
+            .map(|m| m.as_str().to_string());
+    }
+    let caps = PATH_LINE.captures(previous_line?.trim())?;
+    Some(caps[1].to_string())
+}
+
+/// Parses Markdown fences named by their info string (```` ```src/main.rs ````,
+/// ```` ```rust path=src/main.rs ````) or by a path on the line before them.
+fn parse_fences(text: &str) -> Vec<ParsedFile> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut files = Vec::new();
+    let mut previous_line = None;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i].trim_end();
+        let Some(info) = line.strip_prefix("```") else {
+            if !line.trim().is_empty() {
+                previous_line = Some(line);
+            }
+            i += 1;
+            continue;
+        };
+
+        let close = (i + 1..lines.len()).find(|&k| lines[k].trim_end() == "```");
+        let Some(close) = close else {
+            break;
+        };
+        if let Some(path) = fence_path(info, previous_line) {
+            files.push(ParsedFile {
+                path,
+                content: lines[i + 1..close].concat(),
+            });
+        }
+        previous_line = None;
+        i = close + 1;
+    }
+
+    files
+}
+
+/// Extracts files from a response in any of pacont's formats: XML documents, `**path:**`
+/// headers (with or without fences around the bodies) or named Markdown fences.
+pub fn parse_response(text: &str) -> Vec<ParsedFile> {
+    let text = text.replace("\r\n", "\n");
+    let xml = parse_xml(&text);
+    if !xml.is_empty() {
+        return xml;
+    }
+    let headers = parse_headers(&text);
+    if !headers.is_empty() {
+        return headers;
+    }
+    parse_fences(&text)
+}
+
+/// Follows `path` from the canonical directory `resolved` the way the file system would,
+/// resolving every symlink on the way, even one whose target does not exist yet. Fails when a
+/// link leads outside `root`.
+fn resolve_components(
+    mut resolved: PathBuf,
+    path: &Path,
+    root: &Path,
+    hops: &mut usize,
+) -> Result<PathBuf> {
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => resolved.push(component.as_os_str()),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => {
+                let next = resolved.join(name);
+                let is_symlink = fs::symlink_metadata(&next)
+                    .is_ok_and(|metadata| metadata.file_type().is_symlink());
+                if !is_symlink {
+                    resolved = next;
+                    continue;
+                }
+
+                *hops += 1;
+                if *hops > MAX_SYMLINK_HOPS {
+                    anyhow::bail!("Too many levels of symlinks at {}", next.display());
+                }
+                let target = fs::read_link(&next)
+                    .with_context(|| format!("Failed to resolve {}", next.display()))?;
+                resolved = resolve_components(resolved, &target, root, hops)?;
+                if !resolved.starts_with(root) {
+                    anyhow::bail!("{} links outside the target root", next.display());
+                }
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// Resolves `path` under `root`, refusing absolute paths, `..` and symlinks leading elsewhere,
+/// including dangling ones that a write would create a file through.
+pub fn resolve_target(root: &Path, path: &str) -> Result<PathBuf> {
+    let relative = Path::new(path.trim());
+    if relative.as_os_str().is_empty()
+        || relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        anyhow::bail!("Refusing path outside the target root: {}", path);
+    }
+
+    let canonical_root =
+        fs::canonicalize(root).with_context(|| format!("Failed to resolve {}", root.display()))?;
+    let resolved = resolve_components(canonical_root.clone(), relative, &canonical_root, &mut 0)
+        .with_context(|| format!("Refusing path outside the target root: {}", path))?;
+    if !resolved.starts_with(&canonical_root) {
+        anyhow::bail!("Refusing path outside the target root: {}", path);
+    }
+
+    Ok(root.join(relative))
+}
+
+enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffLine<'a>> {
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        return old
+            .iter()
+            .map(|line| DiffLine::Removed(line))
+            .chain(new.iter().map(|line| DiffLine::Added(line)))
+            .collect();
+    }
+
+    // lcs[i][j] is the longest common subsequence of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(DiffLine::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len()
+            && (j == new.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+        {
+            lines.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// A unified diff of `old` against `new` with three lines of context, or an empty string when
+/// they are equal.
+pub fn unified_diff(path: &str, old: Option<&str>, new: &str) -> String {
+    let old_lines: Vec<&str> = old.unwrap_or_default().lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let lines = diff_lines(&old_lines, &new_lines);
+
+    let changes: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, DiffLine::Same(_)))
+        .map(|(index, _)| index)
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &change in &changes {
+        let first = change.saturating_sub(DIFF_CONTEXT);
+        let last = (change + DIFF_CONTEXT).min(lines.len() - 1);
+        match hunks.last_mut() {
+            Some(hunk) if first <= hunk.1 + 1 => hunk.1 = last,
+            _ => hunks.push((first, last)),
+        }
+    }
+
+    let mut diff = match old {
+        Some(_) => format!("--- a/{}\n+++ b/{}\n", path, path),
+        None => format!("--- /dev/null\n+++ b/{}\n", path),
+    };
+    // Line numbers where each diff line sits in the old and new file
+    let mut old_line = 1;
+    let mut new_line = 1;
+    let mut position = 0;
+    for (first, last) in hunks {
+        for line in &lines[position..first] {
+            match line {
+                DiffLine::Same(_) => {
+                    old_line += 1;
+                    new_line += 1;
+                }
+                DiffLine::Removed(_) => old_line += 1,
+                DiffLine::Added(_) => new_line += 1,
+            }
+        }
+        let hunk = &lines[first..=last];
+        let old_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Added(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Removed(_)))
+            .count();
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_count == 0 {
+                old_line - 1
+            } else {
+                old_line
+            },
+            old_count,
+            if new_count == 0 {
+                new_line - 1
+            } else {
+                new_line
+            },
+            new_count
+        ));
+        for line in hunk {
+            let (prefix, text) = match line {
+                DiffLine::Same(text) => (' ', text),
+                DiffLine::Removed(text) => ('-', text),
+                DiffLine::Added(text) => ('+', text),
+            };
+            diff.push(prefix);
+            diff.push_str(text);
+            diff.push('\n');
+        }
+        old_line += old_count;
+        new_line += new_count;
+        position = last + 1;
+    }
+    diff
+}
+
+fn confirm(question: &str, stdin_is_free: bool) -> Result<bool> {
+    eprint!("{} [y/N] ", question);
+    let mut answer = String::new();
+    // The response itself may have come through stdin, so ask the terminal directly
+    match File::open("/dev/tty") {
+        Ok(tty) => BufReader::new(tty).read_line(&mut answer),
+        Err(_) if stdin_is_free => io::stdin().lock().read_line(&mut answer),
+        Err(_) => anyhow::bail!("Cannot ask for confirmation without a terminal, pass --yes"),
+    }
+    .context("Failed to read confirmation")?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Writes files from a pasted response into `args.root` after showing what would change.
+pub fn run_apply(args: &ApplyArgs) -> Result<()> {
+    let response = if args.from_clipboard {
+        paste_from_clipboard()?
+    } else {
+        let mut response = String::new();
+        io::stdin()
+            .read_to_string(&mut response)
+            .context("Failed to read response from stdin")?;
+        response
+    };
+
+    let files = parse_response(&response);
+    if files.is_empty() {
+        anyhow::bail!("No files found in the response");
+    }
+
+    let mut changes = Vec::new();
+    for file in files {
+        let target = match resolve_target(&args.root, &file.path) {
+            Ok(target) => target,
+            Err(e) => {
+                eprintln!("ERROR: {}", e);
+                continue;
+            }
+        };
+        let current = match fs::read_to_string(&target) {
+            Ok(current) => Some(current),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                eprintln!(
+                    "ERROR: Refusing to overwrite {}, which could not be read: {}",
+                    file.path, e
+                );
+                continue;
+            }
+        };
+        if file.content.trim().is_empty()
+            && current
+                .as_deref()
+                .is_some_and(|current| !current.trim().is_empty())
+            && !args.allow_empty
+        {
+            eprintln!(
+                "ERROR: Refusing to empty {}, pass --allow-empty to write it",
+                file.path
+            );
+            continue;
+        }
+        let diff = unified_diff(&file.path, current.as_deref(), &file.content);
+        if diff.is_empty() && current.as_deref() == Some(file.content.as_str()) {
+            eprintln!("UNCHANGED: {}", file.path);
+            continue;
+        }
+        print!("{}", diff);
+        changes.push((target, file));
+    }
+
+    if changes.is_empty() {
+        eprintln!("Nothing to apply.");
+        return Ok(());
+    }
+    if args.dry_run {
+        return Ok(());
+    }
+    let question = format!("Apply changes to {} file(s)?", changes.len());
+    if !args.yes && !confirm(&question, args.from_clipboard)? {
+        eprintln!("Aborted.");
+        return Ok(());
+    }
+
+    for (target, file) in &changes {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        fs::write(target, &file.content)
+            .with_context(|| format!("Failed to write file {}", target.display()))?;
+        eprintln!("WROTE: {}", file.path);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn parsed(path: &str, content: &str) -> ParsedFile {
+        ParsedFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_default_format() {
+        let response = "Here you go:\n**src/cli.rs:**\npub struct Cli;\n\n----------\n**src/main.rs:** [truncated from 10 bytes, 2 lines]\nfn main() {}\n\n";
+
+        assert_eq!(
+            parse_response(response),
+            [
+                parsed("src/cli.rs", "pub struct Cli;\n"),
+                parsed("src/main.rs", "fn main() {}\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_default_format_with_fenced_bodies() {
+        let response = "**src/lib.rs:**\n```rust\npub fn f() {}\n```\n\n**External imports (not included):**\nreact\n\n";
+
+        assert_eq!(
+            parse_response(response),
+            [parsed("src/lib.rs", "pub fn f() {}\n")]
+        );
+    }
+
+    #[test]
+    fn test_parse_default_format_with_surrounding_prose() {
+        let fenced = "Sure! Here is the change.\n\n**src/a.rs:**\n```rust\nfn b() {}\n```\n\nThis renames the function. Let me know!\n";
+
+        assert_eq!(parse_response(fenced), [parsed("src/a.rs", "fn b() {}\n")]);
+
+        // Without a fence only a separator or the next header ends a body
+        let yaml = "**config.yaml:**\nname: demo\n\ndescription: A tool for packing things.\nversion: 2\n\n----------\n**a.rs:**\nfn a() {}\n";
+
+        assert_eq!(
+            parse_response(yaml),
+            [
+                parsed(
+                    "config.yaml",
+                    "name: demo\n\ndescription: A tool for packing things.\nversion: 2\n"
+                ),
+                parsed("a.rs", "fn a() {}\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_default_format_skips_omitted_placeholders() {
+        let response =
+            "**big.txt:** [omitted: 12 bytes, 2 lines]\n----------\n**a.rs:**\nfn a() {}\n";
+
+        assert_eq!(parse_response(response), [parsed("a.rs", "fn a() {}\n")]);
+    }
+
+    #[test]
+    fn test_parse_default_format_skips_error_placeholders() {
+        let response = "**b.txt:** [error: Failed to read file b.txt: stream did not contain valid UTF-8]\n----------\n**a.rs:**\nfn a() {}\n";
+
+        assert_eq!(parse_response(response), [parsed("a.rs", "fn a() {}\n")]);
+    }
+
+    #[test]
+    fn test_parse_default_format_skips_symlink_placeholders() {
+        let response = "**config:** [symlink -> ../shared]\n----------\n**outside.txt:** [test for a.rs; symlink -> ../../outside.txt; not followed: symlink outside the input directory]\n----------\n**a.rs:** [truncated from 10 bytes, 2 lines]\nfn a() {}\n";
+
+        assert_eq!(parse_response(response), [parsed("a.rs", "fn a() {}\n")]);
+    }
+
+    #[test]
+    fn test_parse_xml_documents() {
+        let response = "<documents>\n<document path=\"a.py\">\nprint('a')\n</document>\n<document index=\"2\">\n<source>b/c.py</source>\n<document_content>print('c')\n</document_content>\n</document>\n</documents>\n";
+
+        assert_eq!(
+            parse_response(response),
+            [
+                parsed("a.py", "print('a')\n"),
+                parsed("b/c.py", "print('c')\n")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_markdown_fences() {
+        let response = "Update `src/app.ts`:\n\n```ts\nexport {};\n```\n\n```rust path=src/x.rs\nfn x() {}\n```\n\n```go\nunnamed\n```\n\n```docs/notes.md\n# Notes\n```\n";
+
+        assert_eq!(
+            parse_response(response),
+            [
+                parsed("src/x.rs", "fn x() {}\n"),
+                parsed("docs/notes.md", "# Notes\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_markdown_fence_named_by_previous_line() {
+        let response = "### src/util.go\n```go\npackage util\n```\n";
+
+        assert_eq!(
+            parse_response(response),
+            [parsed("src/util.go", "package util\n")]
+        );
+    }
+
+    #[test]
+    fn test_resolve_target_refuses_escapes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        assert_eq!(
+            resolve_target(root, "src/new.rs").unwrap(),
+            root.join("src/new.rs")
+        );
+        assert!(resolve_target(root, "../outside.rs").is_err());
+        assert!(resolve_target(root, "src/../../outside.rs").is_err());
+        assert!(resolve_target(root, "/etc/passwd").is_err());
+        assert!(resolve_target(root, "").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_target_refuses_symlink_escape() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::os::unix::fs::symlink(outside.path(), root.join("link")).unwrap();
+
+        assert!(resolve_target(root, "link/file.rs").is_err());
+
+        // A dangling link would make the write create its target outside the root
+        let dangling = outside.path().join("missing/pwned.txt");
+        std::os::unix::fs::symlink(&dangling, root.join("notes.txt")).unwrap();
+        std::os::unix::fs::symlink("../../escape.txt", root.join("relative.txt")).unwrap();
+
+        assert!(resolve_target(root, "notes.txt").is_err());
+        assert!(resolve_target(root, "relative.txt").is_err());
+
+        // Links that stay inside the root are fine, whether or not their target exists yet
+        fs::create_dir(root.join("src")).unwrap();
+        std::os::unix::fs::symlink("src", root.join("alias")).unwrap();
+        std::os::unix::fs::symlink("src/later.rs", root.join("later.rs")).unwrap();
+        std::os::unix::fs::symlink("loop", root.join("loop")).unwrap();
+
+        assert_eq!(
+            resolve_target(root, "alias/new.rs").unwrap(),
+            root.join("alias/new.rs")
+        );
+        assert!(resolve_target(root, "later.rs").is_ok());
+        assert!(resolve_target(root, "loop").is_err());
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\n";
+        let new = "a\nb\nc\nd\nE\nf\ng\nh\ni\n";
+
+        assert_eq!(
+            unified_diff("x.txt", Some(old), new),
+            "--- a/x.txt\n+++ b/x.txt\n@@ -2,7 +2,7 @@\n b\n c\n d\n-e\n+E\n f\n g\n h\n"
+        );
+        assert_eq!(unified_diff("x.txt", Some(old), old), "");
+    }
+
+    #[test]
+    fn test_unified_diff_new_file() {
+        assert_eq!(
+            unified_diff("new.txt", None, "one\ntwo\n"),
+            "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n"
+        );
+    }
+}
@@ -1,9 +1,86 @@
-use clap::Parser;
+use crate::encoding::Encoding;
+use crate::grep::parse_grep_pattern;
+use crate::line_endings::EolMode;
+use crate::redaction::{RedactRule, parse_redact_rule};
+use crate::split::{SplitLimit, parse_split_limit};
+use crate::templates::parse_template;
+use crate::truncation::{TruncateSpec, parse_truncate_spec};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use regex::Regex;
 use std::path::PathBuf;
 
+/// What to do with a file that exceeds `--max-file-size` or `--max-file-lines`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OversizePolicy {
+    /// Replace the file with a header noting its original size
+    #[default]
+    Skip,
+    /// Keep the start of the file up to the limits
+    Truncate,
+}
+
+/// Where `--include-errors` sends error reports
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ErrorsTo {
+    /// An error entry in place of each unreadable file, shaped like the other entries
+    #[default]
+    Output,
+    Stderr,
+    Both,
+}
+
+/// Parses a byte size such as `4096`, `512K`, `5M` or `1G` (binary multiples).
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+    let number: u64 = digits
+        .parse()
+        .map_err(|_| format!("invalid size '{}'", value))?;
+    let multiplier: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+        _ => return Err(format!("unknown size unit '{}'", unit)),
+    };
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size '{}' is too large", value))
+}
+
 #[derive(Parser)]
-#[clap(version, about, long_about = None)]
+#[clap(version, about, long_about = None, args_conflicts_with_subcommands = true)]
 pub struct Cli {
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+
+    /// Options of the default `pack` command, so `pacont <paths>` works without naming it
+    #[clap(flatten)]
+    pub pack: PackArgs,
+}
+
+impl Cli {
+    /// The command to run, defaulting to `pack` when none is named.
+    pub fn into_command(self) -> Command {
+        self.command.unwrap_or(Command::Pack(self.pack))
+    }
+}
+
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    /// Bundle files into one text for pasting into a prompt (the default)
+    Pack(PackArgs),
+    /// Print character, word and line counts of what `pack` would output
+    Stats(PackArgs),
+    /// Write files from a response in pacont's formats back into the working tree
+    Apply(ApplyArgs),
+}
+
+#[derive(Args, Clone, Default)]
+pub struct PackArgs {
     /// Paths to directories or files to read
     pub paths: Vec<PathBuf>,
 
@@ -11,10 +88,46 @@ pub struct Cli {
     #[clap(short, long, default_value = "10")]
     pub max_depth: usize,
 
-    /// Include error messages in the output
+    /// Walk into symlinked files and directories, except links out of the directory given and
+    /// links that would loop
+    #[clap(long)]
+    pub follow_symlinks: bool,
+
+    /// Show symlinks that are not followed as `path -> target` entries
+    #[clap(long)]
+    pub show_symlinks: bool,
+
+    /// Report files and directories that could not be read, where --errors-to says
     #[clap(short, long)]
     pub include_errors: bool,
 
+    /// Where --include-errors reports errors: inline in the output, on stderr, or both
+    #[clap(
+        long,
+        value_enum,
+        value_name = "WHERE",
+        default_value = "output",
+        requires = "include_errors"
+    )]
+    pub errors_to: ErrorsTo,
+
+    /// Fail without output if any file or directory could not be read
+    #[clap(long)]
+    pub strict: bool,
+
+    /// Read every file in this encoding instead of detecting it per file
+    #[clap(long, value_enum, value_name = "ENCODING")]
+    pub encoding: Option<Encoding>,
+
+    /// Replace invalid bytes with U+FFFD instead of failing to read the file
+    #[clap(long)]
+    pub lossy: bool,
+
+    /// Line endings of the output, including headers and separators; lf and crlf also end lines
+    /// at lone carriage returns
+    #[clap(long, value_enum, default_value_t = EolMode::Keep)]
+    pub eol: EolMode,
+
     /// Get number of characters and words of output (useful if output could be too long)
     #[clap(short, long)]
     pub output_information: bool,
@@ -22,4 +135,221 @@ pub struct Cli {
     /// Copy the output to the system clipboard instead of printing it
     #[clap(short, long)]
     pub copy: bool,
+
+    /// Write the output to this file instead of printing it
+    #[clap(short = 'O', long, value_name = "FILE", conflicts_with = "copy")]
+    pub output: Option<PathBuf>,
+
+    /// Split the output into numbered part files of at most this size, e.g. 30000chars or 8000tokens (about 4 characters per token)
+    #[clap(long, value_name = "SIZE", value_parser = parse_split_limit, requires = "output", conflicts_with = "template")]
+    pub split_at: Option<SplitLimit>,
+
+    /// For Rust files, also include every module reachable through `mod foo;` declarations
+    #[clap(long)]
+    pub follow_mods: bool,
+
+    /// For JS/TS and Python files, also include local files reachable through imports
+    #[clap(long)]
+    pub follow_imports: bool,
+
+    /// Maximum number of import hops to follow from the entry file
+    #[clap(long, value_name = "N", requires = "follow_imports")]
+    pub import_depth: Option<usize>,
+
+    /// Also include the conventional test files of each selected file (`foo_test.go`, `test_foo.py`, `foo.spec.ts`, `tests/foo.rs`, ...)
+    #[clap(long)]
+    pub with_tests: bool,
+
+    /// List the files that would be included, and why others are skipped, without printing contents
+    #[clap(long, visible_alias = "dry-run", conflicts_with = "output_information")]
+    pub list: bool,
+
+    /// Skip or truncate files larger than this size (e.g. 512K, 5M)
+    #[clap(long, value_parser = parse_size)]
+    pub max_file_size: Option<u64>,
+
+    /// Skip or truncate files with more lines than this
+    #[clap(long)]
+    pub max_file_lines: Option<usize>,
+
+    /// How to handle files over --max-file-size or --max-file-lines
+    #[clap(long, value_enum, default_value_t = OversizePolicy::Skip)]
+    pub oversize: OversizePolicy,
+
+    /// Abort instead of printing or copying if the whole output exceeds this size
+    #[clap(long, value_parser = parse_size)]
+    pub max_total_size: Option<u64>,
+
+    /// Keep only the first and last lines of long files (e.g. head:40,tail:20)
+    #[clap(long, value_parser = parse_truncate_spec)]
+    pub truncate: Option<TruncateSpec>,
+
+    /// Only apply --truncate to files with more lines than this [default: head + tail]
+    #[clap(long, requires = "truncate")]
+    pub truncate_threshold: Option<usize>,
+
+    /// Remove comments from source files of supported languages before output
+    #[clap(long)]
+    pub strip_comments: bool,
+
+    /// Keep only types, signatures and doc comments, replacing function bodies with { ... }
+    /// (Rust, Python, TypeScript/JavaScript and Go)
+    #[clap(long)]
+    pub skeleton: bool,
+
+    /// Trim trailing whitespace, collapse blank lines and remove indentation shared by every line
+    #[clap(long)]
+    pub compact: bool,
+
+    /// With --compact, re-indent leading spaces as tabs of this width (never for Python or YAML)
+    #[clap(long, requires = "compact")]
+    pub indent_tabs: Option<usize>,
+
+    /// Do not replace detected secrets (API keys, tokens, private keys) with [REDACTED:kind]
+    #[clap(long)]
+    pub no_redact: bool,
+
+    /// Replace matches of a regex in every file, as PATTERN=>REPLACEMENT (repeatable)
+    #[clap(long = "redact-regex", value_name = "PATTERN=>REPLACEMENT", value_parser = parse_redact_rule)]
+    pub redact_rules: Vec<RedactRule>,
+
+    /// Also apply --redact-regex rules to displayed file paths
+    #[clap(long, requires = "redact_rules")]
+    pub redact_paths: bool,
+
+    /// Only include files with a match for this regular expression
+    #[clap(long, value_name = "PATTERN", value_parser = parse_grep_pattern)]
+    pub grep: Option<Regex>,
+
+    /// Output only the regions around --grep matches, with N lines of context, instead of whole files
+    #[clap(long, value_name = "N", requires = "grep")]
+    pub grep_context: Option<usize>,
+
+    /// Instructions to print before the bundled files
+    #[clap(long, value_name = "TEXT", conflicts_with = "prompt_file")]
+    pub prompt: Option<String>,
+
+    /// Read the instructions printed before the bundled files from a file
+    #[clap(long, value_name = "FILE")]
+    pub prompt_file: Option<PathBuf>,
+
+    /// A final question to print after the bundled files
+    #[clap(long, value_name = "TEXT")]
+    pub question: Option<String>,
+
+    /// Template file rendered around the bundle, with {{tree}}, {{files}}, {{stats}}, {{date}} and {{git_branch}} placeholders
+    #[clap(long, value_name = "FILE")]
+    pub template: Option<PathBuf>,
+
+    /// Header printed before each file, with {path}, {abs_path}, {lang}, {lines}, {size}, {index} and {note} variables [default: **{path}:**]
+    #[clap(long, value_name = "TEMPLATE", value_parser = parse_template)]
+    pub header_template: Option<String>,
+
+    /// Footer printed after each file, with the same variables as --header-template [default: an empty line]
+    #[clap(long, value_name = "TEMPLATE", value_parser = parse_template)]
+    pub footer_template: Option<String>,
+
+    /// Line printed between files; an empty string prints nothing [default: ----------]
+    #[clap(long, value_name = "TEMPLATE", value_parser = parse_template)]
+    pub separator: Option<String>,
+}
+
+impl PackArgs {
+    pub fn errors_to_stderr(&self) -> bool {
+        self.include_errors && self.errors_to != ErrorsTo::Output
+    }
+
+    pub fn errors_to_output(&self) -> bool {
+        self.include_errors && self.errors_to != ErrorsTo::Stderr
+    }
+}
+
+#[derive(Args, Clone)]
+pub struct ApplyArgs {
+    /// Directory the paths in the response are relative to; files outside it are refused
+    #[clap(long, value_name = "DIR", default_value = ".")]
+    pub root: PathBuf,
+
+    /// Read the response from the clipboard instead of stdin
+    #[clap(long)]
+    pub from_clipboard: bool,
+
+    /// Write the files without asking for confirmation
+    #[clap(short, long)]
+    pub yes: bool,
+
+    /// Only show the diff, without writing anything
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Write files whose body in the response is empty over files that are not
+    #[clap(long)]
+    pub allow_empty: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size_plain_bytes() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+    }
+
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("512K"), Ok(512 * 1024));
+        assert_eq!(parse_size("5m"), Ok(5 * 1024 * 1024));
+        assert_eq!(parse_size("1GiB"), Ok(1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_parse_size_invalid() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("abc").is_err());
+        assert!(parse_size("10X").is_err());
+    }
+
+    fn command(args: &[&str]) -> Command {
+        Cli::try_parse_from(args).unwrap().into_command()
+    }
+
+    #[test]
+    fn test_pack_is_the_default_command() {
+        let Command::Pack(args) = command(&["pacont", "-c", "src", "README.md"]) else {
+            panic!("expected the pack command");
+        };
+        assert!(args.copy);
+        assert_eq!(
+            args.paths,
+            [PathBuf::from("src"), PathBuf::from("README.md")]
+        );
+
+        let Command::Pack(args) = command(&["pacont", "pack", "-c", "src"]) else {
+            panic!("expected the pack command");
+        };
+        assert!(args.copy);
+        assert_eq!(args.paths, [PathBuf::from("src")]);
+    }
+
+    #[test]
+    fn test_parse_subcommands() {
+        assert!(matches!(
+            command(&["pacont", "stats", "src"]),
+            Command::Stats(_)
+        ));
+        let Command::Apply(args) = command(&["pacont", "apply", "--root", "repo", "-y"]) else {
+            panic!("expected the apply command");
+        };
+        assert_eq!(args.root, PathBuf::from("repo"));
+        assert!(args.yes);
+    }
+
+    #[test]
+    fn test_subcommand_name_after_pack_options_is_a_path() {
+        let Command::Pack(args) = command(&["pacont", "-c", "apply"]) else {
+            panic!("expected the pack command");
+        };
+        assert_eq!(args.paths, [PathBuf::from("apply")]);
+    }
 }
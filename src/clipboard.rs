@@ -1,3 +1,4 @@
+use crate::failure::Failure;
 use anyhow::Result;
 #[cfg(target_os = "linux")]
 use std::io::Write;
@@ -54,6 +55,32 @@ fn try_linux_clipboard_commands(content: &str) -> Result<()> {
     anyhow::bail!("No Linux clipboard commands available (xclip, xsel, or wl-copy)")
 }
 
+#[cfg(target_os = "linux")]
+fn read_clipboard_command(command_name: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(command_name)
+        .args(args)
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|_| anyhow::anyhow!("Failed to spawn {}", command_name))?;
+
+    if !output.status.success() {
+        anyhow::bail!("{} command failed", command_name);
+    }
+
+    String::from_utf8(output.stdout)
+        .map_err(|_| anyhow::anyhow!("Clipboard content from {} is not UTF-8", command_name))
+}
+
+#[cfg(target_os = "linux")]
+fn try_linux_paste_commands() -> Result<String> {
+    read_clipboard_command("xclip", &["-selection", "clipboard", "-o"])
+        .or_else(|_| read_clipboard_command("xsel", &["--clipboard", "--output"]))
+        .or_else(|_| read_clipboard_command("wl-paste", &["--no-newline"]))
+        .map_err(|_| {
+            anyhow::anyhow!("No Linux clipboard commands available (xclip, xsel, or wl-paste)")
+        })
+}
+
 fn try_arboard_clipboard(content: String) -> Result<()> {
     use arboard::Clipboard;
 
@@ -75,7 +102,26 @@ pub fn copy_to_clipboard(content: String) -> Result<()> {
     }
 
     // Fallback to arboard for macOS, Windows, or if Linux commands aren't available
-    try_arboard_clipboard(content)
+    try_arboard_clipboard(content).map_err(unavailable)
+}
+
+pub fn paste_from_clipboard() -> Result<String> {
+    #[cfg(target_os = "linux")]
+    if let Ok(content) = try_linux_paste_commands() {
+        return Ok(content);
+    }
+
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|e| unavailable(anyhow::anyhow!("Failed to initialize clipboard: {}", e)))?;
+
+    clipboard
+        .get_text()
+        .map_err(|e| unavailable(anyhow::anyhow!("Failed to read from clipboard: {}", e)))
+}
+
+/// Marks a clipboard error as [`Failure::ClipboardUnavailable`], for its exit code.
+fn unavailable(error: anyhow::Error) -> anyhow::Error {
+    Failure::ClipboardUnavailable(error.to_string()).into()
 }
 
 #[cfg(test)]
@@ -0,0 +1,216 @@
+use crate::apply::run_apply;
+use crate::cli::{Cli, Command, PackArgs};
+use crate::clipboard::copy_to_clipboard;
+use crate::directory_operations::{FileBlock, join_blocks, render_blocks};
+use crate::failure::Failure;
+use crate::formatter::{DefaultFormatter, Formatter};
+use crate::line_endings::{EolMode, normalize_line_endings};
+use crate::listing::list_selection;
+use crate::packer::PackedFile;
+use crate::prompt::{RootFiles, wrap_output, wrap_parts, wraps_output};
+use crate::split::{part_path, split_into_parts};
+use crate::stats::TextStats;
+use crate::utils::{gather_totals, output_information, pack_path};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+/// Runs the command given on the command line.
+pub fn run(cli: Cli) -> Result<()> {
+    match cli.into_command() {
+        Command::Pack(args) => run_pack(&args),
+        Command::Stats(args) => run_pack(&PackArgs {
+            output_information: true,
+            ..args
+        }),
+        Command::Apply(args) => run_apply(&args),
+    }
+}
+
+fn run_pack(cli: &PackArgs) -> Result<()> {
+    if cli.paths.is_empty() {
+        anyhow::bail!("No paths provided. Use --help for usage information.");
+    }
+
+    for path in &cli.paths {
+        if !path.exists() {
+            anyhow::bail!("Path or file does not exist: {}", path.display());
+        }
+    }
+
+    let (buffer, failures) = if cli.output_information {
+        let totals = gather_totals(cli);
+        check_selection(cli, totals.files, &totals.failures)?;
+        (output_information(cli, &totals), totals.failures)
+    } else if cli.list {
+        let listing = list_selection(cli)?;
+        check_selection(cli, listing.files, &listing.failures)?;
+        (listing.text, listing.failures)
+    } else {
+        let formatter = DefaultFormatter::from_args(cli);
+        let bundle = output_blocks(cli, &formatter)?;
+        check_selection(cli, bundle.files(), &bundle.failures)?;
+        if let (Some(limit), Some(output)) = (cli.split_at, &cli.output) {
+            let parts = wrap_parts(cli, split_into_parts(&formatter, &bundle.blocks, limit))?;
+            if parts.len() > 1 {
+                let parts: Vec<String> = parts
+                    .into_iter()
+                    .map(|part| normalize_output(cli, part))
+                    .collect();
+                check_total_size(cli, parts.iter().map(String::len).sum())?;
+                write_parts(output, &parts)?;
+                return check_failures(bundle.failures);
+            }
+        }
+        let content = join_blocks(&formatter, &bundle.blocks);
+        let content = if wraps_output(cli) {
+            wrap_output(cli, content, &bundle.roots, bundle.stats)?
+        } else {
+            content
+        };
+        (content, bundle.failures)
+    };
+
+    let buffer = normalize_output(cli, buffer);
+    check_total_size(cli, buffer.len())?;
+
+    if let Some(output) = &cli.output {
+        fs::write(output, &buffer)
+            .with_context(|| format!("Failed to write output to {}", output.display()))?;
+        eprintln!("Output written to {}.", output.display());
+    } else if !cli.copy {
+        print!("{}", buffer);
+    } else if !buffer.is_empty() {
+        copy_to_clipboard(buffer)?;
+        eprintln!("Output copied to clipboard.");
+    } else {
+        eprintln!("No output to copy to clipboard.");
+    }
+
+    check_failures(failures)
+}
+
+/// Fails before anything is output when no files were selected, or with `--strict` when any
+/// could not be read.
+fn check_selection(cli: &PackArgs, files: usize, failures: &[String]) -> Result<()> {
+    if cli.strict && !failures.is_empty() {
+        return Err(Failure::PartialFailure(failures.to_vec()).into());
+    }
+    if files == 0 && failures.is_empty() {
+        return Err(Failure::NoFiles.into());
+    }
+    Ok(())
+}
+
+/// Fails once the output is written when some files could not be read.
+fn check_failures(failures: Vec<String>) -> Result<()> {
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(Failure::PartialFailure(failures).into())
+    }
+}
+
+/// Gives the whole output the `--eol` line endings, including the headers, separators and
+/// prompt text around the files.
+fn normalize_output(cli: &PackArgs, output: String) -> String {
+    match cli.eol {
+        EolMode::Keep => output,
+        eol => normalize_line_endings(&output, eol),
+    }
+}
+
+fn check_total_size(cli: &PackArgs, size: usize) -> Result<()> {
+    if let Some(max_total_size) = cli.max_total_size
+        && size as u64 > max_total_size
+    {
+        return Err(Failure::BudgetExceeded {
+            size,
+            max: max_total_size,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+fn write_parts(output: &Path, parts: &[String]) -> Result<()> {
+    for (index, part) in parts.iter().enumerate() {
+        let path = part_path(output, index + 1);
+        fs::write(&path, part)
+            .with_context(|| format!("Failed to write output to {}", path.display()))?;
+    }
+    eprintln!(
+        "Output written to {} parts: {} ... {}.",
+        parts.len(),
+        part_path(output, 1).display(),
+        part_path(output, parts.len()).display()
+    );
+    Ok(())
+}
+
+/// The rendered files of all command-line paths
+struct Bundle {
+    blocks: Vec<FileBlock>,
+    stats: TextStats,
+    /// Display paths of the files packed from each path, whether output in full or omitted
+    roots: Vec<RootFiles>,
+    /// Errors of the files and directories that could not be read
+    failures: Vec<String>,
+}
+
+impl Bundle {
+    /// Number of files packed
+    fn files(&self) -> usize {
+        self.roots.iter().map(|root| root.paths.len()).sum()
+    }
+}
+
+fn output_blocks(cli: &PackArgs, formatter: &dyn Formatter) -> Result<Bundle> {
+    let mut bundle = Bundle {
+        blocks: Vec::new(),
+        stats: TextStats::default(),
+        roots: Vec::new(),
+        failures: Vec::new(),
+    };
+    let mut index = 0;
+
+    for path in &cli.paths {
+        match pack_path(cli, path) {
+            Ok(packed) => {
+                packed.report_diagnostics();
+                let mut root = RootFiles {
+                    root: path.display().to_string(),
+                    paths: Vec::new(),
+                };
+                for file in &packed.files {
+                    if file.errors.is_empty() {
+                        root.paths.push(file.display_path.clone());
+                    } else {
+                        bundle.failures.extend(file.errors.iter().cloned());
+                    }
+                }
+                bundle.roots.push(root);
+                bundle.stats += packed.stats();
+                bundle
+                    .blocks
+                    .extend(packed.blocks(cli, formatter, &mut index));
+            }
+            Err(e) => {
+                if cli.errors_to_stderr() {
+                    eprintln!("ERROR processing path {}: {}", path.display(), e);
+                }
+                let failure = PackedFile {
+                    path: path.clone(),
+                    display_path: path.display().to_string(),
+                    errors: vec![format!("{:#}", e)],
+                    ..Default::default()
+                };
+                bundle.failures.extend(failure.errors.iter().cloned());
+                bundle
+                    .blocks
+                    .extend(render_blocks(cli, formatter, &[failure], &mut index));
+            }
+        }
+    }
+    Ok(bundle)
+}
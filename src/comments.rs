@@ -0,0 +1,373 @@
+use crate::language::Language;
+use std::collections::HashSet;
+
+struct Syntax {
+    line_comment: &'static str,
+    block_comment: Option<(&'static str, &'static str)>,
+    nested_blocks: bool,
+    single_quote_escapes: bool,
+    triple_quotes: bool,
+    backtick_strings: bool,
+    backtick_escapes: bool,
+    rust_literals: bool,
+    shell_words: bool,
+}
+
+const C_LIKE: Syntax = Syntax {
+    line_comment: "//",
+    block_comment: Some(("/*", "*/")),
+    nested_blocks: false,
+    single_quote_escapes: true,
+    triple_quotes: false,
+    backtick_strings: false,
+    backtick_escapes: false,
+    rust_literals: false,
+    shell_words: false,
+};
+
+const HASH: Syntax = Syntax {
+    line_comment: "#",
+    block_comment: None,
+    nested_blocks: false,
+    single_quote_escapes: true,
+    triple_quotes: false,
+    backtick_strings: false,
+    backtick_escapes: false,
+    rust_literals: false,
+    shell_words: false,
+};
+
+fn syntax_for(language: Language) -> Syntax {
+    match language {
+        Language::Rust => Syntax {
+            nested_blocks: true,
+            rust_literals: true,
+            ..C_LIKE
+        },
+        Language::C | Language::Cpp | Language::Java => C_LIKE,
+        Language::JavaScript | Language::TypeScript => Syntax {
+            backtick_strings: true,
+            backtick_escapes: true,
+            ..C_LIKE
+        },
+        Language::Go => Syntax {
+            backtick_strings: true,
+            ..C_LIKE
+        },
+        Language::Python => Syntax {
+            triple_quotes: true,
+            ..HASH
+        },
+        Language::Shell => Syntax {
+            single_quote_escapes: false,
+            shell_words: true,
+            ..HASH
+        },
+        Language::Toml => Syntax {
+            single_quote_escapes: false,
+            triple_quotes: true,
+            ..HASH
+        },
+        Language::Yaml => Syntax {
+            single_quote_escapes: false,
+            shell_words: true,
+            ..HASH
+        },
+    }
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+fn line_end(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |offset| start + offset)
+}
+
+/// Returns the index just past the closing `delimiter`, honouring backslash escapes if asked.
+fn quoted_end(bytes: &[u8], start: usize, delimiter: &[u8], escapes: bool) -> usize {
+    let mut i = start + delimiter.len();
+    while i < bytes.len() {
+        if escapes && bytes[i] == b'\\' {
+            i += 2;
+        } else if bytes[i..].starts_with(delimiter) {
+            return i + delimiter.len();
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// Distinguishes Rust char literals (`'a'`, `'\n'`) from lifetimes (`'a`).
+fn rust_char_end(contents: &str, start: usize) -> Option<usize> {
+    let bytes = contents.as_bytes();
+    if bytes.get(start + 1) == Some(&b'\\') {
+        let search_from = (start + 3).min(bytes.len());
+        let close = bytes[search_from..].iter().position(|&b| b == b'\'')?;
+        return Some(search_from + close + 1);
+    }
+    let c = contents[start + 1..].chars().next()?;
+    let after = start + 1 + c.len_utf8();
+    (bytes.get(after) == Some(&b'\'')).then_some(after + 1)
+}
+
+/// Matches `r"..."`, `r#"..."#` and their `b`-prefixed forms.
+fn rust_raw_string_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut i = start;
+    if bytes[i] == b'b' {
+        i += 1;
+    }
+    if bytes.get(i) != Some(&b'r') {
+        return None;
+    }
+    i += 1;
+    let hashes = bytes[i..].iter().take_while(|&&b| b == b'#').count();
+    i += hashes;
+    if bytes.get(i) != Some(&b'"') {
+        return None;
+    }
+    let mut closing = vec![b'"'];
+    closing.extend(std::iter::repeat_n(b'#', hashes));
+    Some(quoted_end(bytes, i, &closing, false))
+}
+
+fn string_end(contents: &str, start: usize, syntax: &Syntax) -> Option<usize> {
+    let bytes = contents.as_bytes();
+    let rest = &bytes[start..];
+    let preceded_by_ident = start > 0 && is_ident_byte(bytes[start - 1]);
+
+    match bytes[start] {
+        b'r' | b'b' if syntax.rust_literals && !preceded_by_ident => {
+            rust_raw_string_end(bytes, start)
+        }
+        b'"' if syntax.triple_quotes && rest.starts_with(b"\"\"\"") => {
+            Some(quoted_end(bytes, start, b"\"\"\"", true))
+        }
+        b'"' => Some(quoted_end(bytes, start, b"\"", true)),
+        b'\'' if syntax.rust_literals => rust_char_end(contents, start),
+        b'\'' if syntax.triple_quotes && rest.starts_with(b"'''") => Some(quoted_end(
+            bytes,
+            start,
+            b"'''",
+            syntax.single_quote_escapes,
+        )),
+        b'\'' => Some(quoted_end(bytes, start, b"'", syntax.single_quote_escapes)),
+        b'`' if syntax.backtick_strings => {
+            Some(quoted_end(bytes, start, b"`", syntax.backtick_escapes))
+        }
+        _ => None,
+    }
+}
+
+fn block_end(bytes: &[u8], start: usize, (open, close): (&str, &str), nested: bool) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(open.as_bytes()) && (nested || depth == 0) {
+            depth += 1;
+            i += open.len();
+        } else if bytes[i..].starts_with(close.as_bytes()) {
+            depth -= 1;
+            i += close.len();
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+fn starts_line_comment(bytes: &[u8], i: usize, syntax: &Syntax) -> bool {
+    if !bytes[i..].starts_with(syntax.line_comment.as_bytes()) {
+        return false;
+    }
+    // In shell, `#` only starts a comment at the beginning of a word (`$#`, `${#x}` are not)
+    !syntax.shell_words
+        || i == 0
+        || matches!(
+            bytes[i - 1],
+            b' ' | b'\t' | b'\n' | b';' | b'|' | b'&' | b'(' | b')'
+        )
+}
+
+fn syntax_comment_end(bytes: &[u8], i: usize, syntax: &Syntax) -> Option<usize> {
+    if starts_line_comment(bytes, i, syntax) {
+        return Some(line_end(bytes, i));
+    }
+    let delimiters = syntax.block_comment?;
+    bytes[i..]
+        .starts_with(delimiters.0.as_bytes())
+        .then(|| block_end(bytes, i, delimiters, syntax.nested_blocks))
+}
+
+/// Returns the index just past the comment starting at `start`, if one does.
+pub fn comment_end(contents: &str, start: usize, language: Language) -> Option<usize> {
+    syntax_comment_end(contents.as_bytes(), start, &syntax_for(language))
+}
+
+/// Returns the index just past the string or char literal starting at `start`, if one does.
+pub fn literal_end(contents: &str, start: usize, language: Language) -> Option<usize> {
+    string_end(contents, start, &syntax_for(language))
+}
+
+/// Appends `chunk` and returns how many lines it completed.
+fn copy_chunk(stripped: &mut String, chunk: &str) -> usize {
+    stripped.push_str(chunk);
+    chunk.matches('\n').count()
+}
+
+/// Drops trailing whitespace left behind by a removed comment, and lines it leaves empty.
+fn tidy_lines(stripped: &str, touched_lines: &HashSet<usize>) -> String {
+    let mut tidied = String::with_capacity(stripped.len());
+    for (index, line) in stripped.split_inclusive('\n').enumerate() {
+        if !touched_lines.contains(&index) {
+            tidied.push_str(line);
+            continue;
+        }
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() {
+            continue;
+        }
+        tidied.push_str(trimmed);
+        if line.ends_with('\n') {
+            tidied.push('\n');
+        }
+    }
+    tidied
+}
+
+/// Removes line and block comments from `contents`, leaving string literals untouched.
+pub fn strip_comments(contents: &str, language: Language) -> String {
+    let syntax = syntax_for(language);
+    let bytes = contents.as_bytes();
+    let mut stripped = String::with_capacity(contents.len());
+    let mut touched_lines = HashSet::new();
+    let mut line = 0;
+    let mut copied_up_to = 0;
+    let mut i = 0;
+
+    if syntax.shell_words && contents.starts_with("#!") {
+        i = line_end(bytes, 0);
+    }
+
+    while i < bytes.len() {
+        if let Some(end) = syntax_comment_end(bytes, i, &syntax) {
+            line += copy_chunk(&mut stripped, &contents[copied_up_to..i]);
+            touched_lines.insert(line);
+            i = end;
+            copied_up_to = i;
+        } else if let Some(end) = string_end(contents, i, &syntax) {
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    copy_chunk(&mut stripped, &contents[copied_up_to..]);
+
+    tidy_lines(&stripped, &touched_lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_rust_line_and_doc_comments() {
+        let source = "//! Crate docs\n/// Adds\nfn add() -> i32 { 1 + 2 } // sum\n";
+        assert_eq!(
+            strip_comments(source, Language::Rust),
+            "fn add() -> i32 { 1 + 2 }\n"
+        );
+    }
+
+    #[test]
+    fn test_strip_rust_nested_block_comments() {
+        let source = "let a = 1; /* outer /* inner */ still comment */ let b = 2;\n";
+        assert_eq!(
+            strip_comments(source, Language::Rust),
+            "let a = 1;  let b = 2;\n"
+        );
+    }
+
+    #[test]
+    fn test_strip_keeps_comment_markers_in_strings() {
+        let source = "let url = \"http://example.com\"; // link\nlet s = \"/* not */\";\n";
+        assert_eq!(
+            strip_comments(source, Language::Rust),
+            "let url = \"http://example.com\";\nlet s = \"/* not */\";\n"
+        );
+    }
+
+    #[test]
+    fn test_strip_rust_raw_strings_chars_and_lifetimes() {
+        let source = "fn f<'a>(x: &'a str) -> char { let r = r#\"// \"raw\"\"#; '/' } // c\n";
+        assert_eq!(
+            strip_comments(source, Language::Rust),
+            "fn f<'a>(x: &'a str) -> char { let r = r#\"// \"raw\"\"#; '/' }\n"
+        );
+    }
+
+    #[test]
+    fn test_strip_multiline_block_comment_header() {
+        let source = "/*\n * License\n */\n#include <stdio.h>\n";
+        assert_eq!(strip_comments(source, Language::C), "#include <stdio.h>\n");
+    }
+
+    #[test]
+    fn test_strip_javascript_template_literal() {
+        let source = "const s = `// keep ${x}`; // drop\n";
+        assert_eq!(
+            strip_comments(source, Language::JavaScript),
+            "const s = `// keep ${x}`;\n"
+        );
+    }
+
+    #[test]
+    fn test_strip_python_comments_keeps_docstrings() {
+        let source =
+            "# header\ndef f():\n    \"\"\"Doc # not a comment\"\"\"\n    return '#'  # hash\n";
+        assert_eq!(
+            strip_comments(source, Language::Python),
+            "def f():\n    \"\"\"Doc # not a comment\"\"\"\n    return '#'\n"
+        );
+    }
+
+    #[test]
+    fn test_strip_shell_keeps_shebang_and_special_hashes() {
+        let source = "#!/bin/sh\n# comment\necho $# ${#arr} 'a # b' # trailing\n";
+        assert_eq!(
+            strip_comments(source, Language::Shell),
+            "#!/bin/sh\necho $# ${#arr} 'a # b'\n"
+        );
+    }
+
+    #[test]
+    fn test_strip_toml_comments() {
+        let source = "# config\n[package]\nname = \"pa#cont\" # name\n";
+        assert_eq!(
+            strip_comments(source, Language::Toml),
+            "[package]\nname = \"pa#cont\"\n"
+        );
+    }
+
+    #[test]
+    fn test_strip_yaml_comments_keep_inline_hashes() {
+        let source = "# settings\nurl: http://host/#anchor # docs\n";
+        assert_eq!(
+            strip_comments(source, Language::Yaml),
+            "url: http://host/#anchor\n"
+        );
+    }
+
+    #[test]
+    fn test_strip_preserves_blank_lines_without_comments() {
+        let source = "fn a() {}\n\nfn b() {}\n";
+        assert_eq!(strip_comments(source, Language::Go), source);
+    }
+}
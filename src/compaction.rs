@@ -0,0 +1,288 @@
+use crate::comments::{comment_end, literal_end};
+use crate::language::Language;
+use regex::Regex;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+/// A YAML line ending in a block scalar indicator such as `key: |`, `- >-` or `|2`
+static BLOCK_SCALAR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^\s*|[:-]\s+)[|>][0-9+-]*(?:\s+#.*)?$").unwrap());
+
+/// Where a line lies relative to the multi-line string literals of a file
+#[derive(Clone, Copy, Default)]
+struct Verbatim {
+    /// The line starts inside a literal, so none of it may change
+    starts_inside: bool,
+    /// The line break ends inside a literal, so trailing whitespace belongs to it
+    ends_inside: bool,
+}
+
+/// Byte ranges of the string literals in `contents` that span more than one line.
+fn multiline_literals(contents: &str, language: Language) -> Vec<Range<usize>> {
+    let mut literals = Vec::new();
+    let mut i = 0;
+    while i < contents.len() {
+        if let Some(end) = comment_end(contents, i, language) {
+            i = end;
+        } else if let Some(end) = literal_end(contents, i, language) {
+            if contents[i..end].contains('\n') {
+                literals.push(i..end);
+            }
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    literals
+}
+
+/// Marks the lines of YAML block scalars: those below a `|` or `>` indicator that are
+/// indented deeper than its line, with blank lines after the last of them left out.
+fn block_scalar_lines(lines: &[&str]) -> Vec<Verbatim> {
+    let mut verbatim = vec![Verbatim::default(); lines.len()];
+    let mut parent_indent = None;
+    let mut last_inside = 0;
+
+    for (index, line) in lines.iter().enumerate() {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        if let Some(parent) = parent_indent {
+            if line.trim().is_empty() || indent > parent {
+                verbatim[index] = Verbatim {
+                    starts_inside: true,
+                    ends_inside: true,
+                };
+                if !line.trim().is_empty() {
+                    last_inside = index;
+                }
+                continue;
+            }
+            parent_indent = None;
+        }
+        for blank in verbatim.iter_mut().take(index).skip(last_inside + 1) {
+            *blank = Verbatim::default();
+        }
+        if BLOCK_SCALAR.is_match(line.trim_end()) {
+            parent_indent = Some(indent);
+            last_inside = index;
+        }
+    }
+    for blank in verbatim.iter_mut().skip(last_inside + 1) {
+        *blank = Verbatim::default();
+    }
+    verbatim
+}
+
+/// Finds the lines of `contents` that are inside multi-line string literals, or inside block
+/// scalars for YAML.
+fn verbatim_lines(contents: &str, lines: &[&str], language: Option<Language>) -> Vec<Verbatim> {
+    let literals = match language {
+        None => return vec![Verbatim::default(); lines.len()],
+        Some(Language::Yaml) => return block_scalar_lines(lines),
+        Some(language) => multiline_literals(contents, language),
+    };
+    let inside = |byte: usize| {
+        literals
+            .iter()
+            .any(|literal| literal.start < byte && byte < literal.end)
+    };
+
+    let mut start = 0;
+    contents
+        .split_inclusive('\n')
+        .map(|line| {
+            let verbatim = Verbatim {
+                starts_inside: inside(start),
+                ends_inside: line.ends_with('\n') && inside(start + line.len() - 1),
+            };
+            start += line.len();
+            verbatim
+        })
+        .collect()
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+fn common_indent<'a>(lines: &[&'a str]) -> &'a str {
+    let mut common: Option<&str> = None;
+    for line in lines.iter().filter(|line| !line.is_empty()) {
+        let indent = leading_whitespace(line);
+        common = Some(match common {
+            None => indent,
+            Some(current) => {
+                let shared = current
+                    .bytes()
+                    .zip(indent.bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                &current[..shared]
+            }
+        });
+    }
+    common.unwrap_or("")
+}
+
+fn spaces_to_tabs(line: &str, tab_width: usize) -> String {
+    let spaces = line.len() - line.trim_start_matches(' ').len();
+    if spaces == 0 || line[spaces..].starts_with('\t') {
+        return line.to_string();
+    }
+    format!(
+        "{}{}{}",
+        "\t".repeat(spaces / tab_width),
+        " ".repeat(spaces % tab_width),
+        &line[spaces..]
+    )
+}
+
+/// Splits a line from `split_inclusive` into its text and its line ending, `\n` or `\r\n`.
+fn split_line_ending(line: &str) -> (&str, &str) {
+    let text = match line.strip_suffix('\n') {
+        Some(text) => text.strip_suffix('\r').unwrap_or(text),
+        None => line,
+    };
+    (text, &line[text.len()..])
+}
+
+/// Shrinks whitespace: trims line ends, removes the indentation shared by every line,
+/// collapses blank-line runs and optionally re-indents with tabs. Tabs are never introduced
+/// for indentation-significant languages such as Python and YAML. Lines inside multi-line
+/// string literals and YAML block scalars are left as they are, and every line keeps its
+/// line ending.
+pub fn compact(contents: &str, language: Option<Language>, tab_width: Option<usize>) -> String {
+    let (lines, endings): (Vec<&str>, Vec<&str>) = contents
+        .split_inclusive('\n')
+        .map(split_line_ending)
+        .unzip();
+    let verbatim = verbatim_lines(contents, &lines, language);
+    let trimmed: Vec<&str> = lines
+        .iter()
+        .zip(&verbatim)
+        .map(|(line, verbatim)| {
+            if verbatim.ends_inside {
+                line
+            } else {
+                line.trim_end()
+            }
+        })
+        .collect();
+    let code: Vec<&str> = trimmed
+        .iter()
+        .zip(&verbatim)
+        .filter(|(_, verbatim)| !verbatim.starts_inside)
+        .map(|(line, _)| *line)
+        .collect();
+    let indent = common_indent(&code);
+    let tab_width = tab_width
+        .filter(|&width| width > 0)
+        .filter(|_| !language.is_some_and(Language::is_indentation_significant));
+
+    let mut compacted = String::with_capacity(contents.len());
+    // The line ending of the first blank line in a run, which the run collapses to
+    let mut pending_blank = None;
+
+    for ((line, verbatim), ending) in trimmed.into_iter().zip(verbatim).zip(endings) {
+        // The last line gets a line break even when the file has none
+        let ending = if ending.is_empty() { "\n" } else { ending };
+        if line.is_empty() && !verbatim.starts_inside {
+            if !compacted.is_empty() && pending_blank.is_none() {
+                pending_blank = Some(ending);
+            }
+            continue;
+        }
+        if let Some(blank) = pending_blank.take() {
+            compacted.push_str(blank);
+        }
+        if verbatim.starts_inside {
+            compacted.push_str(line);
+        } else {
+            let line = line.strip_prefix(indent).unwrap_or(line);
+            match tab_width {
+                Some(width) => compacted.push_str(&spaces_to_tabs(line, width)),
+                None => compacted.push_str(line),
+            }
+        }
+        compacted.push_str(ending);
+    }
+
+    compacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact_collapses_blank_lines_and_trims() {
+        let source = "\n\nfn a() {}   \n\n\n\nfn b() {}\t\n\n\n";
+        assert_eq!(compact(source, None, None), "fn a() {}\n\nfn b() {}\n");
+    }
+
+    #[test]
+    fn test_compact_keeps_line_endings() {
+        let source = "fn a() {}  \r\n\r\n\r\nfn b() {}\nfn c() {}\r\n";
+        assert_eq!(
+            compact(source, Some(Language::Rust), None),
+            "fn a() {}\r\n\r\nfn b() {}\nfn c() {}\r\n"
+        );
+    }
+
+    #[test]
+    fn test_compact_strips_common_indent() {
+        let source = "    def f():\n        return 1\n\n    x = 2\n";
+        assert_eq!(
+            compact(source, Some(Language::Python), None),
+            "def f():\n    return 1\n\nx = 2\n"
+        );
+    }
+
+    #[test]
+    fn test_compact_converts_leading_spaces_to_tabs() {
+        let source = "fn a() {\n    if x {\n        y();\n      }\n}\n";
+        assert_eq!(
+            compact(source, Some(Language::Rust), Some(4)),
+            "fn a() {\n\tif x {\n\t\ty();\n\t  }\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_compact_never_tabs_python_or_yaml() {
+        let python = "def f():\n    return 1\n";
+        assert_eq!(compact(python, Some(Language::Python), Some(4)), python);
+
+        let yaml = "root:\n  child:\n    - item\n";
+        assert_eq!(compact(yaml, Some(Language::Yaml), Some(2)), yaml);
+    }
+
+    #[test]
+    fn test_compact_mixed_indent_keeps_shared_prefix_only() {
+        let source = "\t  a\n\t b\n";
+        assert_eq!(compact(source, None, None), " a\nb\n");
+    }
+
+    #[test]
+    fn test_compact_keeps_python_triple_quoted_strings() {
+        let source = "    def f():\n        return \"\"\"a  \n\n\n  b\n\"\"\"   \n\n\n    x = 1\n";
+        assert_eq!(
+            compact(source, Some(Language::Python), None),
+            "def f():\n    return \"\"\"a  \n\n\n  b\n\"\"\"\n\nx = 1\n"
+        );
+    }
+
+    #[test]
+    fn test_compact_keeps_yaml_block_scalars() {
+        let source =
+            "run: |\n  echo a  \n\n\n    echo b\nfolded: >-\n  text\n\n\nnext:   \n  - 1\n";
+        assert_eq!(
+            compact(source, Some(Language::Yaml), None),
+            "run: |\n  echo a  \n\n\n    echo b\nfolded: >-\n  text\n\nnext:\n  - 1\n"
+        );
+    }
+
+    #[test]
+    fn test_compact_empty() {
+        assert_eq!(compact("", None, None), "");
+        assert_eq!(compact("\n\n  \n", None, None), "");
+    }
+}
@@ -1,80 +1,394 @@
-use crate::cli::Cli;
-use crate::file_operations::process_file_content;
-use crate::utils::separator;
-use anyhow::Result;
-use std::path::Path;
+use crate::cli::PackArgs;
+use crate::file_operations::{pack_file, redacted_display_path};
+use crate::formatter::Formatter;
+use crate::packer::PackedFile;
+use crate::redaction::apply_redact_rules;
+use crate::selection::{SkipReason, SkippedFile, skip_notice};
+use crate::test_companions::{Companion, find_test_companions};
+use crate::utils::canonical_path;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-fn handle_file_error(path: &Path, error: &anyhow::Error, include_errors: bool) {
-    if include_errors {
+fn handle_file_error(path: &Path, error: &anyhow::Error, to_stderr: bool) {
+    if to_stderr {
         eprintln!("**{}:**", path.display());
         eprintln!("ERROR: Failed to process file: {}", error);
     }
 }
 
-fn handle_walk_error(directory: &Path, error: &walkdir::Error, include_errors: bool) {
-    if include_errors {
-        eprintln!(
-            "ERROR: Failed to read entry in {}: {}",
-            directory.display(),
-            error
-        );
+/// Reports entries that could not be read or resolved on stderr.
+pub fn report_failures(failures: &[PackedFile]) {
+    for failure in failures {
+        for error in &failure.errors {
+            eprintln!("ERROR: {}", error);
+        }
     }
 }
 
-fn should_add_separator(buffer: &str, content: &str, output_information: bool) -> bool {
-    !buffer.is_empty() && !content.is_empty() && !output_information
+/// What a walk of one command-line directory found
+#[derive(Default)]
+pub struct Walk {
+    pub files: Vec<PathBuf>,
+    /// `--show-symlinks` entries for the symlinks that were not followed
+    pub symlinks: Vec<PackedFile>,
+    /// Directory entries that could not be read
+    pub failures: Vec<PackedFile>,
+    /// Symlinks `--follow-symlinks` refused to follow, with the reason
+    pub skipped: Vec<SkippedFile>,
+    /// Notices about the symlinks that were not followed, when errors go to stderr
+    pub diagnostics: Vec<String>,
+    /// Every directory walked so far, so one reached by several links is walked once
+    visited: HashSet<DirectoryId>,
+}
+
+#[cfg(unix)]
+type DirectoryId = (u64, u64);
+#[cfg(not(unix))]
+type DirectoryId = PathBuf;
+
+/// Identifies a directory however it is reached: by device and inode on Unix, and by
+/// canonical path elsewhere.
+#[cfg(unix)]
+fn directory_id(path: &Path) -> Option<DirectoryId> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path)
+        .ok()
+        .map(|metadata| (metadata.dev(), metadata.ino()))
 }
 
-pub fn process_directory(cli: &Cli, directory: &Path) -> Result<(String, usize, usize, usize)> {
-    let mut buffer = String::new();
-    let mut total_chars = 0;
-    let mut total_words = 0;
-    let mut total_lines = 0;
+#[cfg(not(unix))]
+fn directory_id(path: &Path) -> Option<DirectoryId> {
+    fs::canonicalize(path).ok()
+}
 
-    for entry_result in WalkDir::new(directory).max_depth(cli.max_depth) {
+/// Walks `directory` down to `--max-depth`. With `--follow-symlinks`, symlinks are walked
+/// as if they were the files and directories they point to, except for links out of
+/// `directory`, links to a directory they are inside of and links to a directory already
+/// walked. Symlinks are followed after the directory holding them has been walked, so files
+/// reached both directly and through a link are found under their real path.
+pub fn walk_directory(cli: &PackArgs, directory: &Path) -> Walk {
+    let mut walk = Walk::default();
+    let root = fs::canonicalize(directory).unwrap_or_else(|_| directory.to_path_buf());
+    walk_into(cli, directory, &root, directory, cli.max_depth, &mut walk);
+    walk
+}
+
+fn walk_into(
+    cli: &PackArgs,
+    directory: &Path,
+    root: &Path,
+    start: &Path,
+    max_depth: usize,
+    walk: &mut Walk,
+) {
+    if max_depth > 0
+        && let Some(id) = directory_id(start)
+    {
+        walk.visited.insert(id);
+    }
+    let mut links = Vec::new();
+    let mut entries = WalkDir::new(start).max_depth(max_depth).into_iter();
+    while let Some(entry_result) = entries.next() {
         let entry = match entry_result {
             Ok(entry) => entry,
             Err(e) => {
-                handle_walk_error(directory, &e, cli.include_errors);
+                let path = e.path().unwrap_or(start).to_path_buf();
+                walk.failures.push(PackedFile {
+                    display_path: redacted_display_path(cli, &path, directory)
+                        .unwrap_or_else(|_| path.display().to_string()),
+                    path,
+                    errors: vec![format!(
+                        "Failed to read entry in {}: {}",
+                        directory.display(),
+                        e
+                    )],
+                    ..Default::default()
+                });
                 continue;
             }
         };
 
-        if !entry.file_type().is_file() {
-            continue;
+        // Only directories whose entries are walked count as visited
+        if entry.file_type().is_dir() {
+            if entry.depth() > 0
+                && entry.depth() < max_depth
+                && let Some(id) = directory_id(entry.path())
+                && !walk.visited.insert(id)
+            {
+                entries.skip_current_dir();
+            }
+        } else if entry.file_type().is_file() {
+            walk.files.push(entry.into_path());
+        } else if entry.path_is_symlink() && entry.depth() > 0 {
+            let remaining_depth = max_depth - entry.depth();
+            links.push((entry.into_path(), remaining_depth));
         }
+    }
 
-        let (content, chars, words, lines) =
-            match process_file_content(entry.path(), directory, cli.output_information) {
-                Ok(result) => result,
-                Err(e) => {
-                    handle_file_error(entry.path(), &e, cli.include_errors);
-                    continue;
-                }
-            };
+    for (link, remaining_depth) in links {
+        visit_symlink(cli, directory, root, &link, remaining_depth, walk);
+    }
+}
 
-        total_chars += chars;
-        total_words += words;
-        total_lines += lines;
+/// Follows a symlink found by a walk when `--follow-symlinks` allows it, and otherwise
+/// leaves it out or, with `--show-symlinks`, keeps a placeholder for it.
+fn visit_symlink(
+    cli: &PackArgs,
+    directory: &Path,
+    root: &Path,
+    link: &Path,
+    remaining_depth: usize,
+    walk: &mut Walk,
+) {
+    let refusal = if cli.follow_symlinks {
+        match fs::canonicalize(link) {
+            Err(_) => Some(SkipReason::BrokenSymlink),
+            Ok(target) if !target.starts_with(root) => Some(SkipReason::SymlinkOutsideRoot),
+            Ok(target) if target.is_file() => {
+                walk.files.push(link.to_path_buf());
+                return;
+            }
+            Ok(_) if is_own_ancestor(directory, link) => Some(SkipReason::SymlinkLoop),
+            Ok(_) if directory_id(link).is_some_and(|id| walk.visited.contains(&id)) => {
+                Some(SkipReason::DuplicateDirectory)
+            }
+            Ok(_) => {
+                walk_into(cli, directory, root, link, remaining_depth, walk);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    let display_path =
+        redacted_display_path(cli, link, directory).unwrap_or_else(|_| link.display().to_string());
+    if let Some(reason) = refusal {
+        if cli.errors_to_stderr() {
+            walk.diagnostics.push(skip_notice(link, reason));
+        }
+        walk.skipped.push(SkippedFile {
+            path: link.to_path_buf(),
+            display_path: display_path.clone(),
+            reason,
+        });
+    }
+    if cli.show_symlinks {
+        let target = fs::read_link(link)
+            .map(|target| target.display().to_string())
+            .unwrap_or_default();
+        let target = if cli.redact_paths {
+            apply_redact_rules(target, &cli.redact_rules).0
+        } else {
+            target
+        };
+        let note = match refusal {
+            Some(reason) => format!("symlink -> {}; not followed: {}", target, reason),
+            None => format!("symlink -> {}", target),
+        };
+        walk.symlinks.push(PackedFile {
+            display_path,
+            path: link.to_path_buf(),
+            note: Some(note),
+            omitted: true,
+            ..Default::default()
+        });
+    }
+}
 
-        if should_add_separator(&buffer, &content, cli.output_information) {
-            buffer.push_str(&separator());
-            buffer.push('\n');
+/// Whether a symlinked directory is one of the directories between it and `directory`, which
+/// would make following it loop forever.
+fn is_own_ancestor(directory: &Path, link: &Path) -> bool {
+    let Some(target) = directory_id(link) else {
+        return false;
+    };
+    for ancestor in link.ancestors().skip(1) {
+        if directory_id(ancestor) == Some(target) {
+            return true;
+        }
+        if ancestor == directory {
+            break;
         }
+    }
+    false
+}
+
+/// One file's rendered output, kept apart from the others until the bundle is assembled
+pub struct FileBlock {
+    pub display_path: String,
+    pub content: String,
+}
 
-        buffer.push_str(&content);
+/// Canonicalizes `files` and their test companions, with the deepest directory holding
+/// `base_path` and every companion, for tests found outside the directory walked, such as
+/// `tests/foo.rs` for `src`.
+fn rebase_on_tests(
+    files: &[PathBuf],
+    companions: Vec<Companion>,
+    base_path: &Path,
+) -> (Vec<PathBuf>, Vec<Companion>, PathBuf) {
+    let companions: Vec<Companion> = companions
+        .into_iter()
+        .map(|companion| Companion {
+            path: canonical_path(&companion.path),
+            source: canonical_path(&companion.source),
+        })
+        .collect();
+
+    let mut base = canonical_path(base_path);
+    for companion in &companions {
+        while !companion.path.starts_with(&base) && base.pop() {}
     }
+    (
+        files.iter().map(|file| canonical_path(file)).collect(),
+        companions,
+        base,
+    )
+}
 
-    Ok((buffer, total_chars, total_words, total_lines))
+/// Packs `files` in order, showing their paths relative to `base_path`. With `--with-tests`,
+/// their test files follow, noting which file pulled each one in. Files that fail to read are
+/// kept with their errors, and skipped files are left out, with a notice added to
+/// `diagnostics` when errors go to stderr. Skipped files and files omitted for their size are
+/// added to `skipped`. `explicit` is the canonical path of a file named on the command line,
+/// which is packed even if it looks sensitive.
+pub fn pack_files(
+    cli: &PackArgs,
+    files: &[PathBuf],
+    base_path: &Path,
+    explicit: Option<&Path>,
+    diagnostics: &mut Vec<String>,
+    skipped: &mut Vec<SkippedFile>,
+) -> Vec<PackedFile> {
+    let companions = if cli.with_tests {
+        find_test_companions(files)
+    } else {
+        Vec::new()
+    };
+    let rebased;
+    let (files, companions, base_path) = if companions
+        .iter()
+        .all(|companion| companion.path.starts_with(base_path))
+    {
+        (files, companions, base_path)
+    } else {
+        rebased = rebase_on_tests(files, companions, base_path);
+        (&rebased.0[..], rebased.1, rebased.2.as_path())
+    };
+    let selection = files
+        .iter()
+        .map(|path| (path, None, explicit == Some(canonical_path(path).as_path())))
+        .chain(companions.iter().map(|companion| {
+            let source = redacted_display_path(cli, &companion.source, base_path)
+                .unwrap_or_else(|_| companion.source.display().to_string());
+            (&companion.path, Some(format!("test for {}", source)), false)
+        }));
+
+    let mut packed = Vec::new();
+    for (path, annotation, explicit) in selection {
+        match pack_file(cli, path, base_path, annotation.as_deref(), explicit) {
+            Ok(Ok(file)) => {
+                if file.omitted {
+                    skipped.push(SkippedFile {
+                        path: path.clone(),
+                        display_path: file.display_path.clone(),
+                        reason: SkipReason::TooLarge,
+                    });
+                }
+                packed.push(file);
+            }
+            Ok(Err(reason)) => {
+                if cli.errors_to_stderr() {
+                    diagnostics.push(skip_notice(path, reason));
+                }
+                skipped.push(SkippedFile {
+                    path: path.clone(),
+                    display_path: redacted_display_path(cli, path, base_path)
+                        .unwrap_or_else(|_| path.display().to_string()),
+                    reason,
+                });
+            }
+            Err(e) => {
+                handle_file_error(path, &e, cli.errors_to_stderr());
+                packed.push(PackedFile {
+                    path: path.clone(),
+                    display_path: redacted_display_path(cli, path, base_path)
+                        .unwrap_or_else(|_| path.display().to_string()),
+                    errors: vec![format!("{:#}", e)],
+                    ..Default::default()
+                });
+            }
+        }
+    }
+    packed
+}
+
+/// Renders packed files into blocks, numbering them on from `index`. Files the formatter
+/// outputs nothing for are left out.
+pub fn render_blocks(
+    cli: &PackArgs,
+    formatter: &dyn Formatter,
+    files: &[PackedFile],
+    index: &mut usize,
+) -> Vec<FileBlock> {
+    if cli.output_information {
+        return Vec::new();
+    }
+
+    let mut blocks = Vec::new();
+    for file in files {
+        let content = if file.errors.is_empty() {
+            formatter.file(file, *index + 1)
+        } else {
+            formatter.error(file, *index + 1)
+        };
+        if !content.is_empty() {
+            *index += 1;
+            blocks.push(FileBlock {
+                display_path: file.display_path.clone(),
+                content,
+            });
+        }
+    }
+    blocks
+}
+
+/// Joins file blocks with the formatter's separator between them, adding its start and end.
+pub fn join_blocks(formatter: &dyn Formatter, blocks: &[FileBlock]) -> String {
+    let body = blocks
+        .iter()
+        .map(|block| block.content.as_str())
+        .collect::<Vec<_>>()
+        .join(&formatter.separator());
+    format!("{}{}{}", formatter.start(), body, formatter.end())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::formatter::DefaultFormatter;
+    use crate::stats::TextStats;
     use std::fs;
     use tempfile::TempDir;
 
+    fn process_directory(cli: &PackArgs, directory: &Path) -> (String, TextStats) {
+        let files = pack_files(
+            cli,
+            &walk_directory(cli, directory).files,
+            directory,
+            None,
+            &mut Vec::new(),
+            &mut Vec::new(),
+        );
+        let formatter = DefaultFormatter::from_args(cli);
+        (
+            join_blocks(&formatter, &render_blocks(cli, &formatter, &files, &mut 0)),
+            files.iter().map(|file| file.stats).sum(),
+        )
+    }
+
     #[test]
     fn test_process_directory_basic() {
         let temp_dir = TempDir::new().unwrap();
@@ -84,23 +398,21 @@ mod tests {
         fs::write(&file1, "Content 1\n").unwrap();
         fs::write(&file2, "Content 2\n").unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![],
             max_depth: 10,
-            include_errors: false,
-            output_information: false,
-            copy: false,
+            ..Default::default()
         };
 
-        let (content, chars, words, lines) = process_directory(&cli, temp_dir.path()).unwrap();
+        let (content, stats) = process_directory(&cli, temp_dir.path());
 
         assert!(content.contains("**file1.txt:**"));
         assert!(content.contains("**file2.txt:**"));
         assert!(content.contains("Content 1"));
         assert!(content.contains("Content 2"));
-        assert_eq!(chars, 20); // "Content 1\n" + "Content 2\n"
-        assert_eq!(words, 4);
-        assert_eq!(lines, 2);
+        assert_eq!(stats.chars, 20); // "Content 1\n" + "Content 2\n"
+        assert_eq!(stats.words, 4);
+        assert_eq!(stats.lines, 2);
     }
 
     #[test]
@@ -112,21 +424,19 @@ mod tests {
         fs::write(temp_dir.path().join("root.txt"), "Root\n").unwrap();
         fs::write(subdir.join("nested.txt"), "Nested\n").unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![],
             max_depth: 10,
-            include_errors: false,
-            output_information: false,
-            copy: false,
+            ..Default::default()
         };
 
-        let (content, chars, words, lines) = process_directory(&cli, temp_dir.path()).unwrap();
+        let (content, stats) = process_directory(&cli, temp_dir.path());
 
         assert!(content.contains("**root.txt:**"));
         assert!(content.contains("**subdir/nested.txt:**"));
-        assert_eq!(chars, 12); // "Root\n" + "Nested\n"
-        assert_eq!(words, 2);
-        assert_eq!(lines, 2);
+        assert_eq!(stats.chars, 12); // "Root\n" + "Nested\n"
+        assert_eq!(stats.words, 2);
+        assert_eq!(stats.lines, 2);
     }
 
     #[test]
@@ -138,15 +448,13 @@ mod tests {
         fs::write(temp_dir.path().join("root.txt"), "Root\n").unwrap();
         fs::write(subdir.join("nested.txt"), "Nested\n").unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![],
             max_depth: 0,
-            include_errors: false,
-            output_information: false,
-            copy: false,
+            ..Default::default()
         };
 
-        let (content, _chars, _words, _lines) = process_directory(&cli, temp_dir.path()).unwrap();
+        let (content, _stats) = process_directory(&cli, temp_dir.path());
 
         // With max_depth 0, we should not traverse into the directory at all
         assert_eq!(content, "");
@@ -161,15 +469,13 @@ mod tests {
         fs::write(temp_dir.path().join("root.txt"), "Root\n").unwrap();
         fs::write(subdir.join("nested.txt"), "Nested\n").unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![],
             max_depth: 1,
-            include_errors: false,
-            output_information: false,
-            copy: false,
+            ..Default::default()
         };
 
-        let (content, _chars, _words, _lines) = process_directory(&cli, temp_dir.path()).unwrap();
+        let (content, _stats) = process_directory(&cli, temp_dir.path());
 
         // With max_depth 1, we should see root.txt but not nested.txt
         assert!(content.contains("**root.txt:**"));
@@ -181,21 +487,20 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         fs::write(temp_dir.path().join("file.txt"), "Test\n").unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![],
             max_depth: 10,
-            include_errors: false,
             output_information: true,
-            copy: false,
+            ..Default::default()
         };
 
-        let (content, chars, words, lines) = process_directory(&cli, temp_dir.path()).unwrap();
+        let (content, stats) = process_directory(&cli, temp_dir.path());
 
         // In output_information mode, content should be empty
         assert_eq!(content, "");
-        assert_eq!(chars, 5); // "Test\n"
-        assert_eq!(words, 1);
-        assert_eq!(lines, 1);
+        assert_eq!(stats.chars, 5); // "Test\n"
+        assert_eq!(stats.words, 1);
+        assert_eq!(stats.lines, 1);
     }
 
     #[test]
@@ -204,37 +509,188 @@ mod tests {
         fs::write(temp_dir.path().join("file1.txt"), "A\n").unwrap();
         fs::write(temp_dir.path().join("file2.txt"), "B\n").unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![],
             max_depth: 10,
-            include_errors: false,
-            output_information: false,
-            copy: false,
+            ..Default::default()
         };
 
-        let (content, _chars, _words, _lines) = process_directory(&cli, temp_dir.path()).unwrap();
+        let (content, _stats) = process_directory(&cli, temp_dir.path());
 
         // Check that separator is present between files
         assert!(content.contains("--------"));
     }
 
+    #[cfg(unix)]
+    fn symlinked_tree() -> TempDir {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        fs::create_dir_all(root.join("shared")).unwrap();
+        fs::write(root.join("shared/config.toml"), "a = 1\n").unwrap();
+        fs::write(temp_dir.path().join("outside.txt"), "secret\n").unwrap();
+        fs::create_dir(root.join("app")).unwrap();
+        symlink("../shared", root.join("app/config")).unwrap();
+        symlink("..", root.join("app/parent")).unwrap();
+        symlink("../../outside.txt", root.join("app/outside.txt")).unwrap();
+        symlink("missing", root.join("app/broken")).unwrap();
+        temp_dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_directory_follow_symlinks() {
+        let temp_dir = symlinked_tree();
+        let root = temp_dir.path().join("root");
+        let cli = PackArgs {
+            max_depth: 10,
+            follow_symlinks: true,
+            ..Default::default()
+        };
+
+        let walk = walk_directory(&cli, &root);
+        let mut files: Vec<_> = walk
+            .files
+            .iter()
+            .map(|file| file.strip_prefix(&root).unwrap().display().to_string())
+            .collect();
+        files.sort();
+
+        // The linked directory is walked once, under its real path
+        assert_eq!(files, ["shared/config.toml"]);
+        assert!(walk.failures.is_empty());
+        assert!(walk.symlinks.is_empty());
+        assert!(walk.skipped.contains(&SkippedFile {
+            path: root.join("app/config"),
+            display_path: "app/config".to_string(),
+            reason: SkipReason::DuplicateDirectory,
+        }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_directory_follows_diamond_links_once() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("deep/er/lib")).unwrap();
+        fs::write(root.join("deep/er/lib/util.rs"), "fn util() {}\n").unwrap();
+        symlink("deep/er/lib", root.join("a")).unwrap();
+        symlink("deep/er/lib", root.join("b")).unwrap();
+        // Too shallow to reach util.rs other than through the links
+        let cli = PackArgs {
+            max_depth: 3,
+            follow_symlinks: true,
+            ..Default::default()
+        };
+
+        let walk = walk_directory(&cli, root);
+
+        assert_eq!(walk.files.len(), 1);
+        assert_eq!(walk.skipped.len(), 1);
+        assert_eq!(walk.skipped[0].reason, SkipReason::DuplicateDirectory);
+        let mut reached = vec![
+            walk.files[0].parent().unwrap().to_path_buf(),
+            walk.skipped[0].path.clone(),
+        ];
+        reached.sort();
+        assert_eq!(reached, [root.join("a"), root.join("b")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_directory_show_symlinks() {
+        let temp_dir = symlinked_tree();
+        let root = temp_dir.path().join("root");
+        let cli = PackArgs {
+            max_depth: 10,
+            show_symlinks: true,
+            ..Default::default()
+        };
+
+        let walk = walk_directory(&cli, &root);
+        let mut notes: Vec<_> = walk
+            .symlinks
+            .iter()
+            .map(|link| format!("{} {}", link.display_path, link.note.as_deref().unwrap()))
+            .collect();
+        notes.sort();
+
+        assert_eq!(walk.files, [root.join("shared/config.toml")]);
+        assert_eq!(
+            notes,
+            [
+                "app/broken symlink -> missing",
+                "app/config symlink -> ../shared",
+                "app/outside.txt symlink -> ../../outside.txt",
+                "app/parent symlink -> ..",
+            ]
+        );
+
+        let following = PackArgs {
+            follow_symlinks: true,
+            ..cli
+        };
+        let walk = walk_directory(&following, &root);
+        let mut notes: Vec<_> = walk
+            .symlinks
+            .iter()
+            .map(|link| link.note.clone().unwrap())
+            .collect();
+        notes.sort();
+
+        assert_eq!(
+            notes,
+            [
+                "symlink -> ../../outside.txt; not followed: symlink outside the input directory",
+                "symlink -> ../shared; not followed: directory already included",
+                "symlink -> ..; not followed: symlink loop",
+                "symlink -> missing; not followed: broken symlink",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_process_directory_with_tests_outside() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("Cargo.toml"), "[package]\n").unwrap();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::create_dir(root.join("tests")).unwrap();
+        fs::write(root.join("src/foo.rs"), "pub fn foo() {}\n").unwrap();
+        fs::write(root.join("tests/foo.rs"), "#[test]\nfn foo() {}\n").unwrap();
+
+        let cli = PackArgs {
+            max_depth: 10,
+            with_tests: true,
+            ..Default::default()
+        };
+
+        let (content, _stats) = process_directory(&cli, &root.join("src"));
+
+        assert_eq!(
+            content,
+            "**src/foo.rs:**\npub fn foo() {}\n\n----------\n**tests/foo.rs:** [test for src/foo.rs]\n#[test]\nfn foo() {}\n\n"
+        );
+    }
+
     #[test]
     fn test_process_directory_empty() {
         let temp_dir = TempDir::new().unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![],
             max_depth: 10,
-            include_errors: false,
-            output_information: false,
-            copy: false,
+            ..Default::default()
         };
 
-        let (content, chars, words, lines) = process_directory(&cli, temp_dir.path()).unwrap();
+        let (content, stats) = process_directory(&cli, temp_dir.path());
 
         assert_eq!(content, "");
-        assert_eq!(chars, 0);
-        assert_eq!(words, 0);
-        assert_eq!(lines, 0);
+        assert_eq!(stats.chars, 0);
+        assert_eq!(stats.words, 0);
+        assert_eq!(stats.lines, 0);
     }
 }
@@ -0,0 +1,229 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::path::Path;
+
+/// Text encodings files can be read in, detected per file unless `--encoding` names one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    #[value(name = "utf-8")]
+    Utf8,
+    #[value(name = "utf-16le")]
+    Utf16Le,
+    #[value(name = "utf-16be")]
+    Utf16Be,
+    #[value(name = "latin1")]
+    Latin1,
+    #[value(name = "windows-1252")]
+    Windows1252,
+}
+
+/// What Windows-1252 maps bytes 0x80 to 0x9F to; the five unassigned ones keep their Latin-1
+/// control characters, and every other byte is the same as in Latin-1.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+/// The encoding given by a byte order mark at the start of `bytes`, and the mark's length.
+fn bom(bytes: &[u8]) -> Option<(Encoding, usize)> {
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        Some((Encoding::Utf8, 3))
+    } else if bytes.starts_with(&[0xFF, 0xFE]) {
+        Some((Encoding::Utf16Le, 2))
+    } else if bytes.starts_with(&[0xFE, 0xFF]) {
+        Some((Encoding::Utf16Be, 2))
+    } else {
+        None
+    }
+}
+
+/// Recognizes UTF-16 from a byte order mark, or from mostly ASCII text whose zero bytes all
+/// fall on one side of each pair.
+pub fn sniff_utf16(head: &[u8]) -> Option<Encoding> {
+    if let Some((encoding, _)) = bom(head) {
+        return (encoding != Encoding::Utf8).then_some(encoding);
+    }
+
+    let pairs = head.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+    let zeros_at = |offset: usize| {
+        head.chunks_exact(2)
+            .filter(|pair| pair[offset] == 0)
+            .count()
+    };
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    if odd * 2 > pairs && even == 0 {
+        Some(Encoding::Utf16Le)
+    } else if even * 2 > pairs && odd == 0 {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Guesses the encoding of a file's contents. Text that is not valid UTF-8 is taken for
+/// UTF-8 with stray invalid bytes when it has any valid multi-byte characters, and for
+/// Windows-1252 (or Latin-1 when no byte is in 0x80 to 0x9F) otherwise.
+pub fn detect_encoding(bytes: &[u8]) -> Encoding {
+    if let Some((encoding, _)) = bom(bytes) {
+        return encoding;
+    }
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return encoding;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return Encoding::Utf8;
+    }
+    let has_multibyte = bytes.utf8_chunks().any(|chunk| !chunk.valid().is_ascii());
+    if has_multibyte {
+        Encoding::Utf8
+    } else if bytes.iter().any(|byte| (0x80..0xA0).contains(byte)) {
+        Encoding::Windows1252
+    } else {
+        Encoding::Latin1
+    }
+}
+
+/// Decodes `bytes` to a string, dropping any byte order mark. With `lossy`, invalid sequences
+/// become U+FFFD instead of failing.
+pub fn decode(bytes: &[u8], encoding: Encoding, lossy: bool) -> Result<String> {
+    let bytes = match bom(bytes) {
+        Some((bom_encoding, len)) if bom_encoding == encoding => &bytes[len..],
+        _ => bytes,
+    };
+
+    match encoding {
+        Encoding::Utf8 if lossy => Ok(String::from_utf8_lossy(bytes).into_owned()),
+        Encoding::Utf8 => String::from_utf8(bytes.to_vec())
+            .map_err(|e| anyhow::anyhow!("{} (use --lossy to replace invalid bytes)", e)),
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| match encoding {
+                    Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                    _ => u16::from_be_bytes([pair[0], pair[1]]),
+                })
+                .collect();
+            let odd_length = bytes.len() % 2 == 1;
+            if lossy {
+                let mut text = String::from_utf16_lossy(&units);
+                if odd_length {
+                    text.push(char::REPLACEMENT_CHARACTER);
+                }
+                Ok(text)
+            } else if odd_length {
+                anyhow::bail!("odd number of bytes for UTF-16 (use --lossy to replace them)")
+            } else {
+                String::from_utf16(&units)
+                    .map_err(|e| anyhow::anyhow!("{} (use --lossy to replace them)", e))
+            }
+        }
+        Encoding::Latin1 => Ok(bytes.iter().map(|&byte| char::from(byte)).collect()),
+        Encoding::Windows1252 => Ok(bytes
+            .iter()
+            .map(|&byte| match byte {
+                0x80..=0x9F => WINDOWS_1252_HIGH[usize::from(byte - 0x80)],
+                _ => char::from(byte),
+            })
+            .collect()),
+    }
+}
+
+/// Reads a file as text in `encoding`, or in the encoding detected from its contents.
+pub fn read_text(path: &Path, encoding: Option<Encoding>, lossy: bool) -> Result<String> {
+    let bytes =
+        fs::read(path).with_context(|| format!("Failed to read file {}", path.display()))?;
+    let encoding = encoding.unwrap_or_else(|| detect_encoding(&bytes));
+    decode(&bytes, encoding, lossy)
+        .with_context(|| format!("Failed to read file {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn test_detect_encoding() {
+        assert_eq!(detect_encoding(b"plain\n"), Encoding::Utf8);
+        assert_eq!(detect_encoding("café\n".as_bytes()), Encoding::Utf8);
+        assert_eq!(detect_encoding(b"\xEF\xBB\xBFhi"), Encoding::Utf8);
+        assert_eq!(detect_encoding(b"\xFF\xFEh\0i\0"), Encoding::Utf16Le);
+        assert_eq!(detect_encoding(b"\xFE\xFF\0h\0i"), Encoding::Utf16Be);
+        assert_eq!(detect_encoding(&utf16le("no mark\n")), Encoding::Utf16Le);
+        assert_eq!(detect_encoding(b"caf\xe9\n"), Encoding::Latin1);
+        assert_eq!(detect_encoding(b"\x93quoted\x94\n"), Encoding::Windows1252);
+        // Valid multi-byte characters mark a file as UTF-8 with a stray byte
+        assert_eq!(detect_encoding(b"caf\xc3\xa9 caf\xe9\n"), Encoding::Utf8);
+    }
+
+    #[test]
+    fn test_decode_single_byte_encodings() {
+        assert_eq!(
+            decode(b"caf\xe9\n", Encoding::Latin1, false).unwrap(),
+            "café\n"
+        );
+        assert_eq!(
+            decode(b"\x93quoted\x94 \x80 \x81", Encoding::Windows1252, false).unwrap(),
+            "“quoted” € \u{81}"
+        );
+    }
+
+    #[test]
+    fn test_decode_utf16() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(utf16le("héllo\n"));
+        assert_eq!(decode(&bytes, Encoding::Utf16Le, false).unwrap(), "héllo\n");
+
+        let big_endian: Vec<u8> = "hi".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(decode(&big_endian, Encoding::Utf16Be, false).unwrap(), "hi");
+
+        assert!(decode(b"h\0i", Encoding::Utf16Le, false).is_err());
+        assert_eq!(
+            decode(b"h\0i", Encoding::Utf16Le, true).unwrap(),
+            "h\u{FFFD}"
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid_utf8() {
+        let bytes = b"\xEF\xBB\xBFcaf\xc3\xa9 caf\xe9\n";
+
+        let error = decode(bytes, Encoding::Utf8, false).unwrap_err();
+        assert!(error.to_string().contains("--lossy"));
+        assert_eq!(
+            decode(bytes, Encoding::Utf8, true).unwrap(),
+            "café caf\u{FFFD}\n"
+        );
+    }
+
+    #[test]
+    fn test_sniff_utf16() {
+        assert_eq!(sniff_utf16(&utf16le("text")), Some(Encoding::Utf16Le));
+        assert_eq!(sniff_utf16(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), None);
+        assert_eq!(sniff_utf16(b"\xEF\xBB\xBFtext"), None);
+        assert_eq!(sniff_utf16(b""), None);
+    }
+
+    #[test]
+    fn test_read_text() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("latin1.txt");
+        fs::write(&path, b"caf\xe9\n").unwrap();
+
+        assert_eq!(read_text(&path, None, false).unwrap(), "café\n");
+        let error = read_text(&path, Some(Encoding::Utf8), false).unwrap_err();
+        assert!(format!("{:#}", error).starts_with("Failed to read file "));
+        assert_eq!(
+            read_text(&path, Some(Encoding::Utf8), true).unwrap(),
+            "caf\u{FFFD}\n"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+
+/// Exit code for errors without a code of their own
+pub const EXIT_ERROR: u8 = 1;
+
+/// Ways a run can fail that scripts may want to tell apart, each with its own exit code
+#[derive(Debug)]
+pub enum Failure {
+    /// The paths given selected no files to pack
+    NoFiles,
+    /// Some files or directories could not be read; holds their errors
+    PartialFailure(Vec<String>),
+    /// The output is larger than `--max-total-size`
+    BudgetExceeded { size: usize, max: u64 },
+    /// The system clipboard could not be used; holds the reason
+    ClipboardUnavailable(String),
+}
+
+impl Failure {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Failure::NoFiles => 3,
+            Failure::PartialFailure(_) => 4,
+            Failure::BudgetExceeded { .. } => 5,
+            Failure::ClipboardUnavailable(_) => 6,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::NoFiles => write!(f, "No files matched the paths given"),
+            Failure::PartialFailure(errors) => {
+                write!(
+                    f,
+                    "{} {} could not be read",
+                    errors.len(),
+                    if errors.len() == 1 {
+                        "entry"
+                    } else {
+                        "entries"
+                    }
+                )?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
+            Failure::BudgetExceeded { size, max } => write!(
+                f,
+                "Output is {} bytes, which exceeds --max-total-size of {} bytes",
+                size, max
+            ),
+            Failure::ClipboardUnavailable(reason) => {
+                write!(f, "Clipboard unavailable: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// The exit code for an error returned by [`run`](crate::run): the code of its [`Failure`], or
+/// [`EXIT_ERROR`] for any other error.
+pub fn exit_code(error: &anyhow::Error) -> u8 {
+    error
+        .downcast_ref::<Failure>()
+        .map_or(EXIT_ERROR, Failure::exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let codes = [
+            EXIT_ERROR,
+            Failure::NoFiles.exit_code(),
+            Failure::PartialFailure(Vec::new()).exit_code(),
+            Failure::BudgetExceeded { size: 2, max: 1 }.exit_code(),
+            Failure::ClipboardUnavailable(String::new()).exit_code(),
+        ];
+
+        for (i, code) in codes.iter().enumerate() {
+            assert!(!codes[i + 1..].contains(code));
+            // 0 means success and clap exits with 2 on usage errors
+            assert!(*code != 0 && *code != 2);
+        }
+    }
+
+    #[test]
+    fn test_exit_code_of_error() {
+        let failure: Result<(), Failure> = Err(Failure::NoFiles);
+        let wrapped = failure.context("Packing failed").unwrap_err();
+
+        assert_eq!(exit_code(&wrapped), 3);
+        assert_eq!(exit_code(&anyhow::anyhow!("other")), EXIT_ERROR);
+    }
+
+    #[test]
+    fn test_partial_failure_lists_errors() {
+        let failure = Failure::PartialFailure(vec![
+            "Failed to read file a.txt".to_string(),
+            "Failed to read file b.txt".to_string(),
+        ]);
+
+        assert_eq!(
+            failure.to_string(),
+            "2 entries could not be read\n  Failed to read file a.txt\n  Failed to read file b.txt"
+        );
+    }
+}
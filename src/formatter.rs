@@ -0,0 +1,276 @@
+use crate::cli::PackArgs;
+use crate::language::Language;
+use crate::packer::PackedFile;
+use crate::redaction::{RedactRule, apply_redact_rules};
+use crate::templates::{FileVariables, render_file_template};
+use crate::utils::separator_line;
+use std::fs;
+
+/// Turns packed files into the text of a bundle. Each hook returns the text to output at that
+/// point; files whose hook returns an empty string are left out, and take no index.
+pub trait Formatter {
+    /// Text before the first file
+    fn start(&self) -> String {
+        String::new()
+    }
+
+    /// A file's header, contents and footer. `index` counts the files output so far, from 1.
+    fn file(&self, file: &PackedFile, index: usize) -> String;
+
+    /// A file that could not be read, described by its `errors`. Left out by default.
+    fn error(&self, _file: &PackedFile, _index: usize) -> String {
+        String::new()
+    }
+
+    /// Text between two files
+    fn separator(&self) -> String;
+
+    /// Text after the last file
+    fn end(&self) -> String {
+        String::new()
+    }
+}
+
+/// pacont's own `**path:**` style, or the `--header-template` / `--footer-template` given
+#[derive(Clone, Debug)]
+pub struct DefaultFormatter {
+    header_template: Option<String>,
+    footer_template: Option<String>,
+    separator: String,
+    /// Whether unreadable files get an entry, from `--include-errors`
+    inline_errors: bool,
+    /// `--redact-regex` rules, when `--redact-paths` also applies them to `{abs_path}`
+    path_rules: Vec<RedactRule>,
+}
+
+impl Default for DefaultFormatter {
+    fn default() -> Self {
+        Self::from_args(&PackArgs::default())
+    }
+}
+
+impl DefaultFormatter {
+    pub(crate) fn from_args(cli: &PackArgs) -> Self {
+        Self {
+            header_template: cli.header_template.clone(),
+            footer_template: cli.footer_template.clone(),
+            separator: separator_line(cli),
+            inline_errors: cli.errors_to_output(),
+            path_rules: if cli.redact_paths {
+                cli.redact_rules.clone()
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    fn uses_templates(&self) -> bool {
+        self.header_template.is_some() || self.footer_template.is_some()
+    }
+
+    fn variables<'a>(
+        &self,
+        file: &'a PackedFile,
+        abs_path: &'a str,
+        index: usize,
+    ) -> FileVariables<'a> {
+        FileVariables {
+            path: &file.display_path,
+            abs_path,
+            lang: Language::from_path(&file.path).map_or("", Language::name),
+            lines: file.content.lines().count(),
+            size: file.content.len(),
+            index,
+            note: file.note.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+fn format_file_header(display_path: &str, note: Option<&str>) -> String {
+    match note {
+        Some(note) => format!("**{}:** [{}]\n", display_path, note),
+        None => format!("**{}:**\n", display_path),
+    }
+}
+
+impl Formatter for DefaultFormatter {
+    /// Wraps the contents in the file header and footer. Omitted files keep only their header
+    /// unless templates are in use.
+    fn file(&self, file: &PackedFile, index: usize) -> String {
+        let header = format_file_header(&file.display_path, file.note.as_deref());
+        if !self.uses_templates() {
+            return if file.omitted {
+                header
+            } else {
+                format!("{}{}\n", header, file.content)
+            };
+        }
+
+        let abs_path = fs::canonicalize(&file.path)
+            .unwrap_or_else(|_| file.path.clone())
+            .display()
+            .to_string();
+        let (abs_path, _) = apply_redact_rules(abs_path, &self.path_rules);
+        let variables = self.variables(file, &abs_path, index);
+
+        let header = match &self.header_template {
+            Some(template) => render_file_template(template, &variables),
+            None => header,
+        };
+        let footer = match &self.footer_template {
+            Some(template) => render_file_template(template, &variables),
+            None => "\n".to_string(),
+        };
+        format!("{}{}{}", header, file.content, footer)
+    }
+
+    /// Gives unreadable files the header of an omitted file, noting the error, when
+    /// `--include-errors` puts errors in the output.
+    fn error(&self, file: &PackedFile, index: usize) -> String {
+        if !self.inline_errors {
+            return String::new();
+        }
+        let failure = PackedFile {
+            note: Some(format!("error: {}", file.errors.join("; "))),
+            omitted: true,
+            ..file.clone()
+        };
+        self.file(&failure, index)
+    }
+
+    fn separator(&self) -> String {
+        self.separator.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::directory_operations::{join_blocks, render_blocks};
+    use std::path::PathBuf;
+
+    fn packed(display_path: &str, content: &str) -> PackedFile {
+        PackedFile {
+            path: PathBuf::from(display_path),
+            display_path: display_path.to_string(),
+            content: content.to_string(),
+            ..Default::default()
+        }
+    }
+
+    struct XmlFormatter;
+
+    impl Formatter for XmlFormatter {
+        fn start(&self) -> String {
+            "<documents>\n".to_string()
+        }
+
+        fn file(&self, file: &PackedFile, index: usize) -> String {
+            format!(
+                "<document index=\"{}\" path=\"{}\">\n{}</document>\n",
+                index, file.display_path, file.content
+            )
+        }
+
+        fn error(&self, file: &PackedFile, _index: usize) -> String {
+            format!(
+                "<error path=\"{}\">{}</error>\n",
+                file.display_path,
+                file.errors.join("; ")
+            )
+        }
+
+        fn separator(&self) -> String {
+            String::new()
+        }
+
+        fn end(&self) -> String {
+            "</documents>\n".to_string()
+        }
+    }
+
+    #[test]
+    fn test_default_formatter() {
+        let formatter = DefaultFormatter::default();
+        let omitted = PackedFile {
+            note: Some("omitted: 9 bytes".to_string()),
+            omitted: true,
+            ..packed("big.txt", "")
+        };
+        let files = [packed("a.rs", "fn a() {}\n"), omitted];
+
+        let blocks = render_blocks(&PackArgs::default(), &formatter, &files, &mut 0);
+
+        assert_eq!(
+            join_blocks(&formatter, &blocks),
+            "**a.rs:**\nfn a() {}\n\n----------\n**big.txt:** [omitted: 9 bytes]\n"
+        );
+    }
+
+    #[test]
+    fn test_custom_formatter_hooks() {
+        let unreadable = PackedFile {
+            errors: vec!["Failed to read file b.txt".to_string()],
+            ..packed("b.txt", "")
+        };
+        let files = [packed("a.txt", "a\n"), unreadable, packed("c.txt", "c\n")];
+
+        let blocks = render_blocks(&PackArgs::default(), &XmlFormatter, &files, &mut 0);
+
+        assert_eq!(
+            join_blocks(&XmlFormatter, &blocks),
+            "<documents>\n<document index=\"1\" path=\"a.txt\">\na\n</document>\n<error path=\"b.txt\">Failed to read file b.txt</error>\n<document index=\"3\" path=\"c.txt\">\nc\n</document>\n</documents>\n"
+        );
+    }
+
+    #[test]
+    fn test_default_formatter_inline_errors() {
+        let cli = PackArgs {
+            include_errors: true,
+            ..Default::default()
+        };
+        let unreadable = PackedFile {
+            errors: vec![
+                "Failed to read file b.txt: stream did not contain valid UTF-8".to_string(),
+            ],
+            ..packed("b.txt", "")
+        };
+        let files = [unreadable, packed("c.txt", "c\n")];
+
+        let formatter = DefaultFormatter::from_args(&cli);
+        let blocks = render_blocks(&cli, &formatter, &files, &mut 0);
+        assert_eq!(
+            join_blocks(&formatter, &blocks),
+            "**b.txt:** [error: Failed to read file b.txt: stream did not contain valid UTF-8]\n----------\n**c.txt:**\nc\n\n"
+        );
+
+        let templated = PackArgs {
+            header_template: Some(
+                "<file index=\"{index}\" path=\"{path}\" note=\"{note}\">".to_string(),
+            ),
+            footer_template: Some("</file>".to_string()),
+            ..cli
+        };
+        let formatter = DefaultFormatter::from_args(&templated);
+        let blocks = render_blocks(&templated, &formatter, &files[..1], &mut 0);
+        assert_eq!(
+            blocks[0].content,
+            "<file index=\"1\" path=\"b.txt\" note=\"error: Failed to read file b.txt: stream did not contain valid UTF-8\">\n</file>\n"
+        );
+    }
+
+    #[test]
+    fn test_default_formatter_leaves_out_errors() {
+        let formatter = DefaultFormatter::default();
+        let unreadable = PackedFile {
+            errors: vec!["Failed to read file b.txt".to_string()],
+            ..packed("b.txt", "")
+        };
+        let mut index = 0;
+
+        let blocks = render_blocks(&PackArgs::default(), &formatter, &[unreadable], &mut index);
+
+        assert!(blocks.is_empty());
+        assert_eq!(index, 0);
+    }
+}
@@ -0,0 +1,174 @@
+use regex::Regex;
+use std::ops::Range;
+
+/// Parses the `--grep` pattern.
+pub fn parse_grep_pattern(value: &str) -> Result<Regex, String> {
+    Regex::new(value).map_err(|e| format!("invalid regex: {}", e))
+}
+
+fn region_marker(first: usize, last: usize) -> String {
+    format!("@@ lines {}-{} @@\n", first, last)
+}
+
+/// Keeps only the lines of `contents` with a match for `pattern`, plus `context` lines around
+/// each. Overlapping or adjacent regions are merged, and each region starts with a marker
+/// giving its 1-based line numbers. Returns an empty string when nothing matches.
+///
+/// Regions grow to take in the whole of any `keep_whole` byte range they touch, so that
+/// `transform`, applied to the text of each region, sees secrets spanning lines in full.
+pub fn grep_excerpts(
+    contents: &str,
+    pattern: &Regex,
+    context: usize,
+    keep_whole: &[Range<usize>],
+    mut transform: impl FnMut(String) -> String,
+) -> String {
+    let lines: Vec<&str> = contents.split_inclusive('\n').collect();
+    if lines.is_empty() {
+        return String::new();
+    }
+
+    let mut line_starts = Vec::with_capacity(lines.len());
+    let mut offset = 0;
+    for line in &lines {
+        line_starts.push(offset);
+        offset += line.len();
+    }
+    let line_of = |byte: usize| line_starts.partition_point(|&start| start <= byte) - 1;
+    let lines_of = |range: &Range<usize>| {
+        (
+            line_of(range.start),
+            line_of(range.end.saturating_sub(1).max(range.start)),
+        )
+    };
+
+    let mut regions: Vec<(usize, usize)> = Vec::new();
+    for found in pattern.find_iter(contents) {
+        let (first, last) = lines_of(&found.range());
+        push_region(
+            &mut regions,
+            (
+                first.saturating_sub(context),
+                (last + context).min(lines.len() - 1),
+            ),
+        );
+    }
+
+    let spans: Vec<(usize, usize)> = keep_whole.iter().map(lines_of).collect();
+    loop {
+        let mut widened = Vec::with_capacity(regions.len());
+        for &(mut first, mut last) in &regions {
+            for &(span_first, span_last) in &spans {
+                if span_first <= last && first <= span_last {
+                    first = first.min(span_first);
+                    last = last.max(span_last);
+                }
+            }
+            push_region(&mut widened, (first, last));
+        }
+        if widened == regions {
+            break;
+        }
+        regions = widened;
+    }
+
+    let mut excerpts = String::new();
+    for (first, last) in regions {
+        excerpts.push_str(&region_marker(first + 1, last + 1));
+        excerpts.push_str(&transform(lines[first..=last].concat()));
+        if !excerpts.ends_with('\n') {
+            excerpts.push('\n');
+        }
+    }
+    excerpts
+}
+
+/// Adds a region after those already found, merging it with the last when they overlap or
+/// touch.
+fn push_region(regions: &mut Vec<(usize, usize)>, (first, last): (usize, usize)) {
+    match regions.last_mut() {
+        Some(region) if first <= region.1 + 1 => region.1 = region.1.max(last),
+        _ => regions.push((first, last)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(count: usize) -> String {
+        (1..=count).map(|n| format!("line {}\n", n)).collect()
+    }
+
+    fn excerpts(contents: &str, pattern: &Regex, context: usize) -> String {
+        grep_excerpts(contents, pattern, context, &[], |text| text)
+    }
+
+    #[test]
+    fn test_grep_excerpts_with_context() {
+        let pattern = parse_grep_pattern(r"line (3|9)\b").unwrap();
+
+        assert_eq!(
+            excerpts(&numbered(12), &pattern, 1),
+            "@@ lines 2-4 @@\nline 2\nline 3\nline 4\n@@ lines 8-10 @@\nline 8\nline 9\nline 10\n"
+        );
+    }
+
+    #[test]
+    fn test_grep_excerpts_merges_overlapping_regions() {
+        let pattern = parse_grep_pattern(r"line (3|5)\b").unwrap();
+
+        assert_eq!(
+            excerpts(&numbered(8), &pattern, 1),
+            "@@ lines 2-6 @@\nline 2\nline 3\nline 4\nline 5\nline 6\n"
+        );
+    }
+
+    #[test]
+    fn test_grep_excerpts_clamps_to_file_and_adds_newline() {
+        let pattern = parse_grep_pattern("last").unwrap();
+
+        assert_eq!(
+            excerpts("first\nlast", &pattern, 5),
+            "@@ lines 1-2 @@\nfirst\nlast\n"
+        );
+    }
+
+    #[test]
+    fn test_grep_excerpts_multiline_match_and_no_match() {
+        let pattern = parse_grep_pattern(r"(?s)line 2.*line 3").unwrap();
+        assert_eq!(
+            excerpts(&numbered(5), &pattern, 0),
+            "@@ lines 2-3 @@\nline 2\nline 3\n"
+        );
+
+        let pattern = parse_grep_pattern("absent").unwrap();
+        assert_eq!(excerpts(&numbered(5), &pattern, 2), "");
+    }
+
+    #[test]
+    fn test_grep_excerpts_keeps_spans_whole() {
+        let contents = numbered(12);
+        let pattern = parse_grep_pattern(r"line 9\b").unwrap();
+        // Lines 6 to 8 must be seen together, and line 2 is too far to matter
+        let start = contents.find("line 6").unwrap();
+        let end = contents.find("line 9").unwrap();
+        let far = contents.find("line 2").unwrap();
+
+        assert_eq!(
+            grep_excerpts(
+                &contents,
+                &pattern,
+                1,
+                &[start..end, far..far + 6],
+                |text| text.replace("line 7\n", "")
+            ),
+            "@@ lines 6-10 @@\nline 6\nline 8\nline 9\nline 10\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_grep_pattern_invalid() {
+        assert!(parse_grep_pattern("(unclosed").is_err());
+    }
+}
@@ -0,0 +1,558 @@
+use crate::cli::PackArgs;
+use crate::comments::strip_comments;
+use crate::language::Language;
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+static JS_IMPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?:\bimport\s*\(\s*|\brequire\s*\(\s*|\bfrom\s*|\bimport\s+)["']([^"'\n]+)["']"#)
+        .unwrap()
+});
+static PY_IMPORT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^[ \t]*import[ \t]+([^\n]+)").unwrap());
+static PY_FROM_IMPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^[ \t]*from[ \t]+(\.*)([\w.]*)[ \t]+import[ \t]+(\([^)]*\)|[^\n]+)").unwrap()
+});
+static TRAILING_COMMA: LazyLock<Regex> = LazyLock::new(|| Regex::new(r",(\s*[}\]])").unwrap());
+
+const JS_EXTENSIONS: [&str; 8] = ["ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs"];
+
+/// Files reachable from an entry point, plus the imports that point outside the project
+pub struct ImportGraph {
+    pub files: Vec<PathBuf>,
+    pub external: BTreeSet<String>,
+}
+
+struct TsConfig {
+    base_url: Option<PathBuf>,
+    paths: Vec<(String, Vec<String>)>,
+    dir: PathBuf,
+}
+
+fn load_tsconfig(start: &Path) -> Option<TsConfig> {
+    let config_path = start
+        .ancestors()
+        .map(|dir| dir.join("tsconfig.json"))
+        .find(|candidate| candidate.is_file())?;
+    let source = fs::read_to_string(&config_path).ok()?;
+    // tsconfig.json allows comments and trailing commas
+    let source = strip_comments(&source, Language::JavaScript);
+    let source = TRAILING_COMMA.replace_all(&source, "$1");
+    let config: Value = serde_json::from_str(&source).ok()?;
+    let options = config.get("compilerOptions")?;
+    let dir = config_path.parent()?.to_path_buf();
+
+    let paths = options
+        .get("paths")
+        .and_then(Value::as_object)
+        .map(|paths| {
+            paths
+                .iter()
+                .map(|(pattern, targets)| {
+                    let targets = targets
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect();
+                    (pattern.clone(), targets)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(TsConfig {
+        base_url: options
+            .get("baseUrl")
+            .and_then(Value::as_str)
+            .map(|base_url| dir.join(base_url)),
+        paths,
+        dir,
+    })
+}
+
+impl TsConfig {
+    /// Candidate locations for a non-relative specifier, from `paths` aliases and `baseUrl`.
+    fn alias_candidates(&self, specifier: &str) -> Vec<PathBuf> {
+        let root = self.base_url.as_deref().unwrap_or(&self.dir);
+        let mut candidates = Vec::new();
+
+        for (pattern, targets) in &self.paths {
+            let wildcard = match pattern.split_once('*') {
+                Some((prefix, suffix)) => specifier
+                    .strip_prefix(prefix)
+                    .and_then(|rest| rest.strip_suffix(suffix)),
+                None => (pattern == specifier).then_some(""),
+            };
+            if let Some(wildcard) = wildcard {
+                candidates.extend(
+                    targets
+                        .iter()
+                        .map(|target| root.join(target.replace('*', wildcard))),
+                );
+            }
+        }
+
+        if let Some(base_url) = &self.base_url {
+            candidates.push(base_url.join(specifier));
+        }
+        candidates
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn resolve_js_file(base: &Path) -> Option<PathBuf> {
+    if base.is_file() {
+        return Some(base.to_path_buf());
+    }
+    // TypeScript sources import their compiled names, e.g. `./util.js` for `util.ts`
+    if base
+        .extension()
+        .is_some_and(|extension| matches!(extension.to_str(), Some("js" | "jsx" | "mjs" | "cjs")))
+    {
+        let stem = base.with_extension("");
+        if let Some(found) = ["ts", "tsx", "mts", "cts"]
+            .iter()
+            .map(|extension| stem.with_extension(extension))
+            .find(|candidate| candidate.is_file())
+        {
+            return Some(found);
+        }
+    }
+    JS_EXTENSIONS
+        .iter()
+        .map(|extension| with_suffix(base, &format!(".{}", extension)))
+        .chain(
+            JS_EXTENSIONS
+                .iter()
+                .map(|extension| base.join(format!("index.{}", extension))),
+        )
+        .find(|candidate| candidate.is_file())
+}
+
+fn js_package_name(specifier: &str) -> String {
+    let segments = if specifier.starts_with('@') { 2 } else { 1 };
+    specifier
+        .split('/')
+        .take(segments)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn js_dependencies(
+    file: &Path,
+    source: &str,
+    tsconfig: Option<&TsConfig>,
+    external: &mut BTreeSet<String>,
+) -> Vec<PathBuf> {
+    let source = strip_comments(source, Language::TypeScript);
+    let dir = file.parent().unwrap_or(Path::new("."));
+    let mut local = Vec::new();
+
+    for caps in JS_IMPORT.captures_iter(&source) {
+        let specifier = &caps[1];
+        let resolved = if specifier.starts_with('.') || specifier.starts_with('/') {
+            resolve_js_file(&dir.join(specifier))
+        } else {
+            tsconfig.and_then(|tsconfig| {
+                tsconfig
+                    .alias_candidates(specifier)
+                    .iter()
+                    .find_map(|candidate| resolve_js_file(candidate))
+            })
+        };
+        match resolved {
+            Some(path) => local.push(path),
+            None if specifier.starts_with('.') || specifier.starts_with('/') => {}
+            None => {
+                external.insert(js_package_name(specifier));
+            }
+        }
+    }
+
+    local
+}
+
+/// Files Python loads for `dotted` under `root`: each package's `__init__.py`, then the module.
+fn python_module_files(root: &Path, dotted: &str) -> Option<Vec<PathBuf>> {
+    let parts: Vec<&str> = dotted.split('.').filter(|part| !part.is_empty()).collect();
+    let mut files = Vec::new();
+    let mut dir = root.to_path_buf();
+
+    if parts.is_empty() {
+        let init = dir.join("__init__.py");
+        return init.is_file().then(|| vec![init]);
+    }
+
+    for (index, part) in parts.iter().enumerate() {
+        let is_last = index == parts.len() - 1;
+        let package = dir.join(part);
+        let init = package.join("__init__.py");
+        let module = dir.join(format!("{}.py", part));
+
+        if init.is_file() {
+            files.push(init);
+        } else if is_last && module.is_file() {
+            files.push(module);
+            return Some(files);
+        } else if !package.is_dir() {
+            return None;
+        }
+        dir = package;
+    }
+
+    (!files.is_empty()).then_some(files)
+}
+
+fn python_import_names(names: &str) -> Vec<&str> {
+    names
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .filter_map(|name| name.split_whitespace().next())
+        .filter(|name| *name != "*")
+        .collect()
+}
+
+/// Directories that absolute imports resolve against: the entry's directory and the parent of
+/// the outermost package containing it.
+fn python_roots(entry: &Path) -> Vec<PathBuf> {
+    let entry_dir = entry.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let mut package_root = entry_dir.clone();
+    while package_root.join("__init__.py").is_file() {
+        match package_root.parent() {
+            Some(parent) => package_root = parent.to_path_buf(),
+            None => break,
+        }
+    }
+    if package_root == entry_dir {
+        vec![entry_dir]
+    } else {
+        vec![entry_dir, package_root]
+    }
+}
+
+fn python_dependencies(
+    file: &Path,
+    source: &str,
+    roots: &[PathBuf],
+    external: &mut BTreeSet<String>,
+) -> Vec<PathBuf> {
+    let source = strip_comments(source, Language::Python);
+    let dir = file.parent().unwrap_or(Path::new("."));
+    let mut local = Vec::new();
+
+    let mut resolve_absolute = |dotted: &str, local: &mut Vec<PathBuf>| match roots
+        .iter()
+        .find_map(|root| python_module_files(root, dotted))
+    {
+        Some(files) => local.extend(files),
+        None => {
+            let top_level = dotted.split('.').next().unwrap_or(dotted);
+            external.insert(top_level.to_string());
+        }
+    };
+
+    for caps in PY_IMPORT.captures_iter(&source) {
+        for module in python_import_names(&caps[1]) {
+            resolve_absolute(module, &mut local);
+        }
+    }
+
+    for caps in PY_FROM_IMPORT.captures_iter(&source) {
+        let dots = caps[1].len();
+        let module = &caps[2];
+        let names = python_import_names(&caps[3]);
+
+        if dots == 0 {
+            let before = local.len();
+            for name in &names {
+                if let Some(files) = roots
+                    .iter()
+                    .find_map(|root| python_module_files(root, &format!("{}.{}", module, name)))
+                {
+                    local.extend(files);
+                }
+            }
+            if local.len() == before {
+                resolve_absolute(module, &mut local);
+            }
+            continue;
+        }
+
+        let Some(base) = dir.ancestors().nth(dots - 1) else {
+            continue;
+        };
+        let mut found_submodule = false;
+        for name in &names {
+            let dotted = format!("{}.{}", module, name);
+            if let Some(files) = python_module_files(base, &dotted) {
+                local.extend(files);
+                found_submodule = true;
+            }
+        }
+        if !found_submodule && let Some(files) = python_module_files(base, module) {
+            local.extend(files);
+        }
+    }
+
+    local
+}
+
+/// Whether `path` is an entry file whose imports `--follow-imports` should expand.
+pub fn follows_imports(cli: &PackArgs, path: &Path) -> bool {
+    cli.follow_imports
+        && matches!(
+            Language::from_path(path),
+            Some(Language::JavaScript | Language::TypeScript | Language::Python)
+        )
+}
+
+/// Follows local imports from `entry` breadth-first, up to `max_depth` hops when given.
+pub fn collect_import_graph(entry: &Path, max_depth: Option<usize>) -> Result<ImportGraph> {
+    let entry = fs::canonicalize(entry)
+        .with_context(|| format!("Failed to resolve {}", entry.display()))?;
+    let tsconfig = load_tsconfig(entry.parent().unwrap_or(Path::new(".")));
+    let python_roots = python_roots(&entry);
+
+    let mut graph = ImportGraph {
+        files: Vec::new(),
+        external: BTreeSet::new(),
+    };
+    let mut visited = HashSet::from([entry.clone()]);
+    let mut queue = VecDeque::from([(entry.clone(), 0)]);
+
+    while let Some((file, depth)) = queue.pop_front() {
+        graph.files.push(file.clone());
+        if max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            continue;
+        }
+
+        let source = match fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(e) if file == entry => {
+                return Err(e).with_context(|| format!("Failed to read file {}", file.display()));
+            }
+            // The file stays in the graph and is packed with its error
+            Err(_) => continue,
+        };
+
+        let dependencies = match Language::from_path(&file) {
+            Some(Language::JavaScript | Language::TypeScript) => {
+                js_dependencies(&file, &source, tsconfig.as_ref(), &mut graph.external)
+            }
+            Some(Language::Python) => {
+                python_dependencies(&file, &source, &python_roots, &mut graph.external)
+            }
+            _ => Vec::new(),
+        };
+
+        for dependency in dependencies {
+            if let Ok(dependency) = fs::canonicalize(&dependency)
+                && visited.insert(dependency.clone())
+            {
+                queue.push_back((dependency, depth + 1));
+            }
+        }
+    }
+
+    Ok(graph)
+}
+
+/// The deepest directory containing every file, used as the base for display paths.
+pub fn common_ancestor(files: &[PathBuf]) -> PathBuf {
+    let mut ancestor = files
+        .first()
+        .and_then(|file| file.parent())
+        .unwrap_or(Path::new(""))
+        .to_path_buf();
+    for file in files {
+        while !file.starts_with(&ancestor) {
+            match ancestor.parent() {
+                Some(parent) => ancestor = parent.to_path_buf(),
+                None => break,
+            }
+        }
+    }
+    ancestor
+}
+
+pub fn format_external_imports(external: &BTreeSet<String>) -> String {
+    let names: Vec<&str> = external.iter().map(String::as_str).collect();
+    format!(
+        "**External imports (not included):**\n{}\n\n",
+        names.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::directory_operations::join_blocks;
+    use crate::formatter::DefaultFormatter;
+    use crate::utils::pack_path;
+    use tempfile::TempDir;
+
+    fn names(graph: &ImportGraph) -> Vec<String> {
+        let base = common_ancestor(&graph.files);
+        graph
+            .files
+            .iter()
+            .map(|file| file.strip_prefix(&base).unwrap().display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_js_import_graph() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src/components")).unwrap();
+        fs::write(
+            root.join("src/app.ts"),
+            "import React from 'react';\nimport { Button } from './components';\nimport './styles.css';\nconst util = require(\"./util.js\");\n// import './commented';\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/components/index.tsx"),
+            "export * from \"./Button\";\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/components/Button.tsx"),
+            "import { css } from '@emotion/react';\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/util.ts"), "export const x = 1;\n").unwrap();
+        fs::write(root.join("src/styles.css"), "body {}\n").unwrap();
+        fs::write(root.join("src/unused.ts"), "").unwrap();
+
+        let graph = collect_import_graph(&root.join("src/app.ts"), None).unwrap();
+
+        assert_eq!(
+            names(&graph),
+            [
+                "app.ts",
+                "components/index.tsx",
+                "styles.css",
+                "util.ts",
+                "components/Button.tsx"
+            ]
+        );
+        assert_eq!(
+            graph.external.iter().collect::<Vec<_>>(),
+            ["@emotion/react", "react"]
+        );
+    }
+
+    #[test]
+    fn test_js_import_graph_tsconfig_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src/lib")).unwrap();
+        fs::write(
+            root.join("tsconfig.json"),
+            "{\n  // comment\n  \"compilerOptions\": {\n    \"baseUrl\": \".\",\n    \"paths\": { \"@lib/*\": [\"src/lib/*\"], },\n  },\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/main.ts"), "import { db } from '@lib/db';\n").unwrap();
+        fs::write(root.join("src/lib/db.ts"), "export const db = 1;\n").unwrap();
+
+        let graph = collect_import_graph(&root.join("src/main.ts"), None).unwrap();
+
+        assert_eq!(names(&graph), ["main.ts", "lib/db.ts"]);
+        assert!(graph.external.is_empty());
+    }
+
+    #[test]
+    fn test_python_import_graph() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("app/models")).unwrap();
+        fs::write(root.join("app/__init__.py"), "").unwrap();
+        fs::write(
+            root.join("app/main.py"),
+            "import os\nimport numpy as np\nfrom . import config\nfrom .models import user\nfrom app.helpers import slugify\n",
+        )
+        .unwrap();
+        fs::write(root.join("app/config.py"), "DEBUG = True\n").unwrap();
+        fs::write(root.join("app/models/__init__.py"), "").unwrap();
+        fs::write(
+            root.join("app/models/user.py"),
+            "from ..config import DEBUG\n",
+        )
+        .unwrap();
+        fs::write(root.join("app/helpers.py"), "def slugify(s): ...\n").unwrap();
+        fs::write(root.join("app/unused.py"), "").unwrap();
+
+        let graph = collect_import_graph(&root.join("app/main.py"), None).unwrap();
+
+        assert_eq!(
+            names(&graph),
+            [
+                "main.py",
+                "config.py",
+                "models/__init__.py",
+                "models/user.py",
+                "__init__.py",
+                "helpers.py"
+            ]
+        );
+        assert_eq!(graph.external.iter().collect::<Vec<_>>(), ["numpy", "os"]);
+    }
+
+    #[test]
+    fn test_import_depth_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("a.js"), "import './b';\n").unwrap();
+        fs::write(root.join("b.js"), "import './c';\n").unwrap();
+        fs::write(root.join("c.js"), "").unwrap();
+
+        let graph = collect_import_graph(&root.join("a.js"), Some(1)).unwrap();
+
+        assert_eq!(names(&graph), ["a.js", "b.js"]);
+    }
+
+    #[test]
+    fn test_common_ancestor() {
+        let files = vec![
+            PathBuf::from("/repo/web/src/app.ts"),
+            PathBuf::from("/repo/shared/util.ts"),
+        ];
+        assert_eq!(common_ancestor(&files), PathBuf::from("/repo"));
+    }
+
+    #[test]
+    fn test_process_path_follow_imports_lists_external() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("main.py"), "import requests\nimport helpers\n").unwrap();
+        fs::write(root.join("helpers.py"), "X = 1\n").unwrap();
+
+        let cli = PackArgs {
+            follow_imports: true,
+            ..Default::default()
+        };
+
+        let packed = pack_path(&cli, &root.join("main.py")).unwrap();
+        let formatter = DefaultFormatter::from_args(&cli);
+        let content = join_blocks(&formatter, &packed.blocks(&cli, &formatter, &mut 0));
+
+        assert!(content.contains("**main.py:**"));
+        assert!(content.contains("**helpers.py:**\nX = 1"));
+        assert!(content.ends_with("**External imports (not included):**\nrequests\n\n"));
+    }
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    C,
+    Cpp,
+    Java,
+    JavaScript,
+    TypeScript,
+    Python,
+    Go,
+    Shell,
+    Toml,
+    Yaml,
+}
+
+impl Language {
+    /// Detects the language of a file from its extension.
+    pub fn from_path(path: &Path) -> Option<Language> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        let language = match extension.as_str() {
+            "rs" => Language::Rust,
+            "c" | "h" => Language::C,
+            "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => Language::Cpp,
+            "java" => Language::Java,
+            "js" | "mjs" | "cjs" | "jsx" => Language::JavaScript,
+            "ts" | "mts" | "cts" | "tsx" => Language::TypeScript,
+            "py" | "pyi" => Language::Python,
+            "go" => Language::Go,
+            "sh" | "bash" | "zsh" => Language::Shell,
+            "toml" => Language::Toml,
+            "yml" | "yaml" => Language::Yaml,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    /// Lowercase name, as used for Markdown code fences.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::Java => "java",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::Python => "python",
+            Language::Go => "go",
+            Language::Shell => "shell",
+            Language::Toml => "toml",
+            Language::Yaml => "yaml",
+        }
+    }
+
+    /// Whether indentation carries meaning, so it must never be rewritten with tabs.
+    pub fn is_indentation_significant(self) -> bool {
+        matches!(self, Language::Python | Language::Yaml)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path_known_extensions() {
+        assert_eq!(
+            Language::from_path(Path::new("src/main.rs")),
+            Some(Language::Rust)
+        );
+        assert_eq!(
+            Language::from_path(Path::new("app.TSX")),
+            Some(Language::TypeScript)
+        );
+        assert_eq!(
+            Language::from_path(Path::new("Cargo.toml")),
+            Some(Language::Toml)
+        );
+        assert_eq!(
+            Language::from_path(Path::new("include/util.hpp")),
+            Some(Language::Cpp)
+        );
+    }
+
+    #[test]
+    fn test_indentation_significant() {
+        assert!(Language::Python.is_indentation_significant());
+        assert!(Language::Yaml.is_indentation_significant());
+        assert!(!Language::Rust.is_indentation_significant());
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!(Language::Rust.name(), "rust");
+        assert_eq!(Language::TypeScript.name(), "typescript");
+    }
+
+    #[test]
+    fn test_from_path_unknown() {
+        assert_eq!(Language::from_path(Path::new("README.md")), None);
+        assert_eq!(Language::from_path(Path::new("Makefile")), None);
+    }
+}
@@ -0,0 +1,44 @@
+//! Gathers files into a single text for pasting into an LLM prompt.
+//!
+//! [`Packer`] collects files into [`PackedFile`]s and renders them the way the `pacont`
+//! command does, or with any [`Formatter`]; [`run`] runs the command itself, and [`exit_code`]
+//! gives the exit code for the errors it returns.
+
+mod apply;
+mod cli;
+mod clipboard;
+mod commands;
+mod comments;
+mod compaction;
+mod directory_operations;
+mod encoding;
+mod failure;
+mod file_operations;
+mod formatter;
+mod grep;
+mod import_graph;
+mod language;
+mod line_endings;
+mod listing;
+mod packer;
+mod prompt;
+mod redaction;
+mod rust_modules;
+mod selection;
+mod skeleton;
+mod split;
+mod stats;
+mod templates;
+mod test_companions;
+mod truncation;
+mod utils;
+
+pub use cli::Cli;
+pub use commands::run;
+pub use encoding::Encoding;
+pub use failure::{EXIT_ERROR, Failure, exit_code};
+pub use formatter::{DefaultFormatter, Formatter};
+pub use line_endings::{EolMode, LineEndings};
+pub use packer::{Packed, PackedFile, Packer};
+pub use selection::{SkipReason, SkippedFile};
+pub use stats::TextStats;
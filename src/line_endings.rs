@@ -0,0 +1,88 @@
+use clap::ValueEnum;
+
+/// How `--eol` rewrites the line endings of output files
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum EolMode {
+    /// End every line with `\n`
+    Lf,
+    /// End every line with `\r\n`
+    Crlf,
+    /// Leave line endings as they are in the file
+    #[default]
+    Keep,
+}
+
+/// The line endings a file had on disk
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEndings {
+    /// Only `\n`, or no line breaks at all
+    #[default]
+    Lf,
+    /// Only `\r\n`
+    Crlf,
+    /// Both `\n` and `\r\n`, or any lone `\r`
+    Mixed,
+}
+
+pub fn detect_line_endings(text: &str) -> LineEndings {
+    let bytes = text.as_bytes();
+    let (mut lf, mut crlf, mut lone_cr) = (0, 0, 0);
+    for (i, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'\n' if i > 0 && bytes[i - 1] == b'\r' => crlf += 1,
+            b'\n' => lf += 1,
+            b'\r' if bytes.get(i + 1) != Some(&b'\n') => lone_cr += 1,
+            _ => {}
+        }
+    }
+
+    match (lf > 0, crlf > 0, lone_cr > 0) {
+        (_, false, false) => LineEndings::Lf,
+        (false, true, false) => LineEndings::Crlf,
+        _ => LineEndings::Mixed,
+    }
+}
+
+/// Rewrites line endings as `mode` says, removing any lone `\r` unless they are kept.
+pub fn normalize_line_endings(text: &str, mode: EolMode) -> String {
+    if mode == EolMode::Keep {
+        return text.to_string();
+    }
+    let lf = text.replace("\r\n", "\n").replace('\r', "");
+    match mode {
+        EolMode::Crlf => lf.replace('\n', "\r\n"),
+        _ => lf,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_line_endings() {
+        assert_eq!(detect_line_endings("a\nb\n"), LineEndings::Lf);
+        assert_eq!(detect_line_endings("no break"), LineEndings::Lf);
+        assert_eq!(detect_line_endings("a\r\nb\r\n"), LineEndings::Crlf);
+        assert_eq!(detect_line_endings("a\r\nb\n"), LineEndings::Mixed);
+        assert_eq!(detect_line_endings("a\rb\n"), LineEndings::Mixed);
+        assert_eq!(detect_line_endings("a\rb\r"), LineEndings::Mixed);
+    }
+
+    #[test]
+    fn test_normalize_line_endings() {
+        let text = "a\r\nb\nc\rd\r\n";
+
+        assert_eq!(normalize_line_endings(text, EolMode::Lf), "a\nb\ncd\n");
+        assert_eq!(
+            normalize_line_endings(text, EolMode::Crlf),
+            "a\r\nb\r\ncd\r\n"
+        );
+        // Text already in a mode is left as it is
+        assert_eq!(
+            normalize_line_endings("a\r\nb\r\n", EolMode::Crlf),
+            "a\r\nb\r\n"
+        );
+        assert_eq!(normalize_line_endings(text, EolMode::Keep), text);
+    }
+}
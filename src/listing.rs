@@ -0,0 +1,300 @@
+use crate::cli::PackArgs;
+use crate::selection::SkipReason;
+use crate::utils::pack_path;
+use anyhow::Result;
+use std::fmt;
+
+/// A file considered by the selection, as shown by `--list`
+pub enum ListEntry {
+    Included {
+        display_path: String,
+        /// The file's note, such as the file a test is for or how it was truncated
+        note: Option<String>,
+    },
+    Skipped {
+        display_path: String,
+        reason: SkipReason,
+    },
+    External(String),
+    /// A file or path that could not be read, with `--errors-to output`
+    Failed {
+        display_path: String,
+        error: String,
+    },
+}
+
+impl fmt::Display for ListEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListEntry::Included {
+                display_path,
+                note: Some(note),
+            } => write!(f, "{} ({})", display_path, note),
+            ListEntry::Included { display_path, .. } => write!(f, "{}", display_path),
+            ListEntry::Skipped {
+                display_path,
+                reason,
+            } => write!(f, "{} (skipped: {})", display_path, reason),
+            ListEntry::External(name) => write!(f, "{} (external)", name),
+            ListEntry::Failed {
+                display_path,
+                error,
+            } => write!(f, "{} (error: {})", display_path, error),
+        }
+    }
+}
+
+/// The `--list` output, with what the checks on the selection need
+pub struct Listing {
+    pub text: String,
+    /// Number of files that would be included
+    pub files: usize,
+    /// Errors of the files and directories that could not be inspected
+    pub failures: Vec<String>,
+}
+
+/// Lists the files that would be included, and why others are skipped. The selection is
+/// packed as it would be for output, and only rendered differently.
+pub fn list_selection(cli: &PackArgs) -> Result<Listing> {
+    let mut entries = Vec::new();
+    let mut failures = Vec::new();
+
+    for path in &cli.paths {
+        let packed = match pack_path(cli, path) {
+            Ok(packed) => packed,
+            Err(e) => {
+                if cli.errors_to_stderr() {
+                    eprintln!("ERROR processing path {}: {}", path.display(), e);
+                }
+                let error = format!("{:#}", e);
+                if cli.errors_to_output() {
+                    entries.push(ListEntry::Failed {
+                        display_path: path.display().to_string(),
+                        error: error.clone(),
+                    });
+                }
+                failures.push(error);
+                continue;
+            }
+        };
+        packed.report_diagnostics();
+
+        for file in &packed.files {
+            if !file.errors.is_empty() {
+                if cli.errors_to_output() {
+                    entries.push(ListEntry::Failed {
+                        display_path: file.display_path.clone(),
+                        error: file.errors.join("; "),
+                    });
+                }
+                failures.extend(file.errors.iter().cloned());
+            } else if !file.omitted {
+                entries.push(ListEntry::Included {
+                    display_path: file.display_path.clone(),
+                    note: file.note.clone(),
+                });
+            }
+        }
+        // --grep narrows the selection rather than skipping files worth reporting
+        entries.extend(
+            packed
+                .skipped
+                .into_iter()
+                .filter(|skipped| skipped.reason != SkipReason::NoMatch)
+                .map(|skipped| ListEntry::Skipped {
+                    display_path: skipped.display_path,
+                    reason: skipped.reason,
+                }),
+        );
+        entries.extend(packed.external.into_iter().map(ListEntry::External));
+    }
+
+    Ok(Listing {
+        text: entries.iter().map(|entry| format!("{}\n", entry)).collect(),
+        files: entries
+            .iter()
+            .filter(|entry| matches!(entry, ListEntry::Included { .. }))
+            .count(),
+        failures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::OversizePolicy;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_list_selection_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let subdir = temp_dir.path().join("subdir");
+        fs::create_dir(&subdir).unwrap();
+        fs::write(temp_dir.path().join("root.txt"), "Root\n").unwrap();
+        fs::write(subdir.join("nested.txt"), "Nested\n").unwrap();
+
+        let cli = PackArgs {
+            paths: vec![temp_dir.path().to_path_buf()],
+            max_depth: 10,
+            list: true,
+            ..Default::default()
+        };
+
+        let listing = list_selection(&cli).unwrap().text;
+
+        assert!(listing.contains("root.txt\n"));
+        assert!(listing.contains("subdir/nested.txt\n"));
+        assert!(!listing.contains("Root"));
+        assert!(!listing.contains("Nested\n"));
+    }
+
+    #[test]
+    fn test_list_selection_binary_reason() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("text.txt"), "Text\n").unwrap();
+        fs::write(temp_dir.path().join("data.bin"), [0u8, 1, 2, 3]).unwrap();
+
+        let cli = PackArgs {
+            paths: vec![temp_dir.path().to_path_buf()],
+            max_depth: 10,
+            list: true,
+            ..Default::default()
+        };
+
+        let listing = list_selection(&cli).unwrap().text;
+
+        assert!(listing.contains("text.txt\n"));
+        assert!(listing.contains("data.bin (skipped: binary)\n"));
+    }
+
+    #[test]
+    fn test_list_selection_sensitive_reason() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join(".env"), "DB_PASSWORD=hunter2\n").unwrap();
+
+        let cli = PackArgs {
+            paths: vec![temp_dir.path().to_path_buf()],
+            max_depth: 10,
+            list: true,
+            ..Default::default()
+        };
+
+        let listing = list_selection(&cli).unwrap().text;
+
+        assert!(listing.contains(".env (skipped: may contain secrets"));
+    }
+
+    #[test]
+    fn test_list_selection_respects_max_depth() {
+        let temp_dir = TempDir::new().unwrap();
+        let subdir = temp_dir.path().join("subdir");
+        fs::create_dir(&subdir).unwrap();
+        fs::write(temp_dir.path().join("root.txt"), "Root\n").unwrap();
+        fs::write(subdir.join("nested.txt"), "Nested\n").unwrap();
+
+        let cli = PackArgs {
+            paths: vec![temp_dir.path().to_path_buf()],
+            max_depth: 1,
+            list: true,
+            ..Default::default()
+        };
+
+        let listing = list_selection(&cli).unwrap().text;
+
+        assert!(listing.contains("root.txt"));
+        assert!(!listing.contains("nested.txt"));
+    }
+
+    #[test]
+    fn test_list_selection_single_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "Hello\n").unwrap();
+
+        let cli = PackArgs {
+            paths: vec![file_path],
+            max_depth: 10,
+            list: true,
+            ..Default::default()
+        };
+
+        assert_eq!(list_selection(&cli).unwrap().text, "test.txt\n");
+    }
+
+    #[test]
+    fn test_list_selection_max_file_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("big.txt"), "1\n2\n3\n4\n").unwrap();
+        fs::write(temp_dir.path().join("small.txt"), "1\n").unwrap();
+
+        let mut cli = PackArgs {
+            paths: vec![temp_dir.path().to_path_buf()],
+            max_depth: 10,
+            list: true,
+            max_file_lines: Some(2),
+            ..Default::default()
+        };
+
+        let listing = list_selection(&cli).unwrap().text;
+        assert!(listing.contains("big.txt (skipped: too large)\n"));
+        assert!(listing.contains("small.txt\n"));
+
+        cli.oversize = OversizePolicy::Truncate;
+        let listing = list_selection(&cli).unwrap().text;
+        assert!(listing.contains("big.txt (truncated from 8 bytes, 4 lines)\n"));
+        assert!(listing.contains("small.txt\n"));
+    }
+
+    #[test]
+    fn test_list_selection_matches_packed_paths_with_tests() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("Cargo.toml"), "").unwrap();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::create_dir(root.join("tests")).unwrap();
+        fs::write(root.join("src").join("lexer.rs"), "pub fn lex() {}\n").unwrap();
+        fs::write(root.join("tests").join("lexer.rs"), "fn lexes() {}\n").unwrap();
+
+        let cli = PackArgs {
+            paths: vec![root.join("src")],
+            max_depth: 10,
+            list: true,
+            with_tests: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            list_selection(&cli).unwrap().text,
+            "src/lexer.rs\ntests/lexer.rs (test for src/lexer.rs)\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_selection_renders_errors_in_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let socket = temp_dir.path().join("app.sock");
+        let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+
+        let cli = PackArgs {
+            paths: vec![socket.clone()],
+            max_depth: 10,
+            list: true,
+            include_errors: true,
+            ..Default::default()
+        };
+
+        let listing = list_selection(&cli).unwrap();
+
+        assert_eq!(listing.files, 0);
+        assert_eq!(listing.failures.len(), 1);
+        assert_eq!(
+            listing.text,
+            format!(
+                "app.sock (error: Path '{}' is neither a file nor a directory)\n",
+                socket.display()
+            )
+        );
+    }
+}
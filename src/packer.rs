@@ -0,0 +1,498 @@
+use crate::cli::{OversizePolicy, PackArgs};
+use crate::directory_operations::{join_blocks, render_blocks};
+use crate::encoding::Encoding;
+use crate::formatter::{DefaultFormatter, Formatter};
+use crate::line_endings::{EolMode, LineEndings};
+use crate::selection::SkippedFile;
+use crate::stats::TextStats;
+use crate::utils::pack_path;
+use anyhow::Result;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// One file gathered by a [`Packer`], after redaction, filtering and truncation
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PackedFile {
+    /// Location of the file on disk
+    pub path: PathBuf,
+    /// Path shown in the file's header, relative to the directory it was found in
+    pub display_path: String,
+    /// Contents as they would be output, without header or footer
+    pub content: String,
+    /// Why the file was included or how it was shortened, e.g. `truncated from 10 bytes, 2 lines`
+    pub note: Option<String>,
+    /// Whether the contents were left out for exceeding the size limits
+    pub omitted: bool,
+    pub stats: TextStats,
+    /// Counts before `--max-file-lines`, `--max-file-size` or `--truncate` shortened the
+    /// contents; zero for files omitted by size without being read
+    pub original_stats: TextStats,
+    /// Line endings the file had on disk, before `--eol` rewrote them
+    pub line_endings: LineEndings,
+    /// Matches replaced by each `--redact-regex` rule in the contents, in the order given
+    pub rule_hits: Vec<usize>,
+    /// Errors that kept the file from being read; the other fields are empty when set
+    pub errors: Vec<String>,
+    /// Notices about the file, such as what was redacted, which `pacont` prints to stderr
+    pub diagnostics: Vec<String>,
+}
+
+/// What a [`Packer`] gathered from its paths
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Packed {
+    /// The files in order, including ones that failed to read, with their `errors` set
+    pub files: Vec<PackedFile>,
+    /// Files left out as binary, sensitive or not matching, or omitted for their size
+    pub skipped: Vec<SkippedFile>,
+    /// Third-party modules found by [`follow_imports`](Packer::follow_imports)
+    pub external_imports: BTreeSet<String>,
+    /// Notices about the paths rather than single files, such as symlinks that were not
+    /// followed, which `pacont` prints to stderr
+    pub diagnostics: Vec<String>,
+}
+
+/// Gathers files into [`PackedFile`]s, the way `pacont pack` does.
+///
+/// ```no_run
+/// use pacont::Packer;
+///
+/// let packed = Packer::new()
+///     .path("src")
+///     .max_depth(3)
+///     .strip_comments(true)
+///     .pack()?;
+/// for file in &packed.files {
+///     println!("{}: {} lines", file.display_path, file.stats.lines);
+/// }
+/// for skipped in &packed.skipped {
+///     println!("{}: skipped, {}", skipped.display_path, skipped.reason);
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct Packer {
+    args: PackArgs,
+    formatter: Option<Arc<dyn Formatter>>,
+}
+
+impl Default for Packer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Packer {
+    pub fn new() -> Self {
+        Self {
+            args: PackArgs {
+                max_depth: 10,
+                ..Default::default()
+            },
+            formatter: None,
+        }
+    }
+
+    /// Adds a directory or file to gather.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.args.paths.push(path.into());
+        self
+    }
+
+    /// Adds several directories or files to gather.
+    pub fn paths<I, P>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.args.paths.extend(paths.into_iter().map(Into::into));
+        self
+    }
+
+    /// Maximum recursion depth for directories [default: 10]
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.args.max_depth = max_depth;
+        self
+    }
+
+    /// Only include files with a match for `pattern`.
+    pub fn grep(mut self, pattern: Regex) -> Self {
+        self.args.grep = Some(pattern);
+        self
+    }
+
+    /// With [`grep`](Self::grep), keep only the regions around matches with this many lines of
+    /// context.
+    pub fn grep_context(mut self, lines: usize) -> Self {
+        self.args.grep_context = Some(lines);
+        self
+    }
+
+    /// Omit files larger than this many bytes, leaving an `omitted` placeholder in their
+    /// place, or keep their start with [`truncate_oversized`](Self::truncate_oversized).
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.args.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Omit files with more than this many lines, or keep their start with
+    /// [`truncate_oversized`](Self::truncate_oversized).
+    pub fn max_file_lines(mut self, lines: usize) -> Self {
+        self.args.max_file_lines = Some(lines);
+        self
+    }
+
+    /// Keep the start of files over [`max_file_lines`](Self::max_file_lines) or
+    /// [`max_file_size`](Self::max_file_size) instead of omitting them.
+    pub fn truncate_oversized(mut self, truncate: bool) -> Self {
+        self.args.oversize = if truncate {
+            OversizePolicy::Truncate
+        } else {
+            OversizePolicy::Skip
+        };
+        self
+    }
+
+    /// Walk into symlinks within the directories given, skipping links out of them and links
+    /// that would loop.
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.args.follow_symlinks = follow;
+        self
+    }
+
+    /// Keep a `symlink -> target` entry for each symlink that is not followed.
+    pub fn show_symlinks(mut self, show: bool) -> Self {
+        self.args.show_symlinks = show;
+        self
+    }
+
+    /// Include the modules a Rust entry file declares instead of only the file itself.
+    pub fn follow_mods(mut self, follow: bool) -> Self {
+        self.args.follow_mods = follow;
+        self
+    }
+
+    /// Include the local files a JavaScript/TypeScript or Python entry file imports.
+    pub fn follow_imports(mut self, follow: bool) -> Self {
+        self.args.follow_imports = follow;
+        self
+    }
+
+    /// Also include the conventional test files of each selected file.
+    pub fn with_tests(mut self, with_tests: bool) -> Self {
+        self.args.with_tests = with_tests;
+        self
+    }
+
+    pub fn strip_comments(mut self, strip: bool) -> Self {
+        self.args.strip_comments = strip;
+        self
+    }
+
+    /// Keep only type definitions, signatures and doc comments.
+    pub fn skeleton(mut self, skeleton: bool) -> Self {
+        self.args.skeleton = skeleton;
+        self
+    }
+
+    /// Trim trailing whitespace, blank line runs and shared indentation.
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.args.compact = compact;
+        self
+    }
+
+    /// Read every file in `encoding` instead of detecting it per file
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.args.encoding = Some(encoding);
+        self
+    }
+
+    /// Replace invalid bytes with U+FFFD instead of failing to read the file
+    pub fn lossy(mut self, lossy: bool) -> Self {
+        self.args.lossy = lossy;
+        self
+    }
+
+    /// Line endings of the packed contents [default: `EolMode::Keep`]
+    pub fn eol(mut self, eol: EolMode) -> Self {
+        self.args.eol = eol;
+        self
+    }
+
+    /// Replace detected secrets with `[REDACTED:kind]` markers [default: true]
+    pub fn redact_secrets(mut self, redact: bool) -> Self {
+        self.args.no_redact = !redact;
+        self
+    }
+
+    /// Header rendered before each file by [`render`](Self::render), with the same variables as
+    /// `--header-template`.
+    pub fn header_template(mut self, template: impl Into<String>) -> Self {
+        self.args.header_template = Some(template.into());
+        self
+    }
+
+    /// Footer rendered after each file by [`render`](Self::render).
+    pub fn footer_template(mut self, template: impl Into<String>) -> Self {
+        self.args.footer_template = Some(template.into());
+        self
+    }
+
+    /// Line rendered between files by [`render`](Self::render); empty for none.
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.args.separator = Some(separator.into());
+        self
+    }
+
+    /// Give files that could not be read an entry in [`render`](Self::render)'s output,
+    /// noting the error.
+    pub fn include_errors(mut self, include: bool) -> Self {
+        self.args.include_errors = include;
+        self
+    }
+
+    /// Renders with `formatter` instead of the `**path:**` style, ignoring
+    /// [`header_template`](Self::header_template), [`footer_template`](Self::footer_template)
+    /// and [`separator`](Self::separator).
+    pub fn formatter(mut self, formatter: impl Formatter + 'static) -> Self {
+        self.formatter = Some(Arc::new(formatter));
+        self
+    }
+
+    /// Gathers the files under every path, in order, with the files that were skipped and
+    /// any diagnostics.
+    pub fn pack(&self) -> Result<Packed> {
+        let mut packed = Packed::default();
+        for path in &self.args.paths {
+            if !path.exists() {
+                anyhow::bail!("Path or file does not exist: {}", path.display());
+            }
+            let path = pack_path(&self.args, path)?;
+            packed.files.extend(path.files);
+            packed.skipped.extend(path.skipped);
+            packed.external_imports.extend(path.external);
+            packed.diagnostics.extend(path.diagnostics);
+        }
+        Ok(packed)
+    }
+
+    /// Formats packed files as one bundle, as `pacont pack` prints them unless a
+    /// [`formatter`](Self::formatter) was given.
+    pub fn render(&self, files: &[PackedFile]) -> String {
+        let default;
+        let formatter: &dyn Formatter = match &self.formatter {
+            Some(formatter) => formatter.as_ref(),
+            None => {
+                default = DefaultFormatter::from_args(&self.args);
+                &default
+            }
+        };
+        join_blocks(
+            formatter,
+            &render_blocks(&self.args, formatter, files, &mut 0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selection::SkipReason;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pack_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("src")).unwrap();
+        fs::write(
+            temp_dir.path().join("src/lib.rs"),
+            "// doc\npub fn f() {}\n",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("image.bin"), [0u8, 159, 146, 150]).unwrap();
+
+        let files = Packer::new()
+            .path(temp_dir.path())
+            .strip_comments(true)
+            .pack()
+            .unwrap()
+            .files;
+
+        assert_eq!(
+            files,
+            [PackedFile {
+                path: temp_dir.path().join("src/lib.rs"),
+                display_path: "src/lib.rs".to_string(),
+                content: "pub fn f() {}\n".to_string(),
+                stats: TextStats {
+                    chars: 14,
+                    words: 4,
+                    lines: 1,
+                },
+                original_stats: TextStats {
+                    chars: 14,
+                    words: 4,
+                    lines: 1,
+                },
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_pack_returns_skipped_files_and_external_imports() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("app.py"), "import requests\n").unwrap();
+        fs::write(temp_dir.path().join("big.txt"), "1\n2\n3\n").unwrap();
+        fs::write(temp_dir.path().join("image.bin"), [0u8, 159, 146, 150]).unwrap();
+
+        let packed = Packer::new()
+            .path(temp_dir.path().join("app.py"))
+            .follow_imports(true)
+            .pack()
+            .unwrap();
+
+        assert_eq!(packed.files.len(), 1);
+        assert_eq!(
+            packed.external_imports,
+            BTreeSet::from(["requests".to_string()])
+        );
+
+        let mut packed = Packer::new()
+            .path(temp_dir.path())
+            .max_file_lines(2)
+            .pack()
+            .unwrap();
+        packed
+            .skipped
+            .sort_by(|a, b| a.display_path.cmp(&b.display_path));
+
+        assert_eq!(
+            packed.skipped,
+            [
+                SkippedFile {
+                    path: temp_dir.path().join("big.txt"),
+                    display_path: "big.txt".to_string(),
+                    reason: SkipReason::TooLarge,
+                },
+                SkippedFile {
+                    path: temp_dir.path().join("image.bin"),
+                    display_path: "image.bin".to_string(),
+                    reason: SkipReason::Binary,
+                },
+            ]
+        );
+        assert!(packed.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_pack_reports_unreadable_files() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("broken.txt"), b"caf\xc3\xa9 caf\xe9\n").unwrap();
+
+        let files = Packer::new().path(temp_dir.path()).pack().unwrap().files;
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].display_path, "broken.txt");
+        assert!(files[0].content.is_empty());
+        assert!(files[0].errors[0].starts_with("Failed to read file"));
+
+        let files = Packer::new()
+            .path(temp_dir.path())
+            .lossy(true)
+            .pack()
+            .unwrap()
+            .files;
+
+        assert_eq!(files[0].content, "café caf\u{FFFD}\n");
+        assert!(files[0].errors.is_empty());
+    }
+
+    #[test]
+    fn test_pack_transcodes_files() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("latin1.txt"), b"caf\xe9\n").unwrap();
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("héllo\n".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        fs::write(temp_dir.path().join("utf16.txt"), utf16).unwrap();
+
+        let mut files = Packer::new().path(temp_dir.path()).pack().unwrap().files;
+        files.sort_by(|a, b| a.display_path.cmp(&b.display_path));
+
+        assert_eq!(files[0].content, "café\n");
+        assert_eq!(files[1].content, "héllo\n");
+
+        let files = Packer::new()
+            .path(temp_dir.path().join("latin1.txt"))
+            .encoding(Encoding::Utf8)
+            .pack()
+            .unwrap()
+            .files;
+
+        assert_eq!(files[0].errors.len(), 1);
+    }
+
+    #[test]
+    fn test_pack_missing_path() {
+        let result = Packer::new().path("/nonexistent/pacont").pack();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_pack_truncates_and_renders() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "1\n2\n3\n").unwrap();
+        fs::write(temp_dir.path().join("b.txt"), "b\n").unwrap();
+
+        let packer = Packer::new()
+            .paths([temp_dir.path().join("a.txt"), temp_dir.path().join("b.txt")])
+            .max_file_lines(1)
+            .truncate_oversized(true)
+            .header_template("## {index}. {path} {note}")
+            .separator("");
+        let files = packer.pack().unwrap().files;
+
+        assert_eq!(
+            files[0].note.as_deref(),
+            Some("truncated from 6 bytes, 3 lines")
+        );
+        assert_eq!(
+            packer.render(&files),
+            "## 1. a.txt truncated from 6 bytes, 3 lines\n1\n\n## 2. b.txt \nb\n\n"
+        );
+        assert_eq!(packer.render(&files), packer.render(&files));
+    }
+
+    struct ListFormatter;
+
+    impl Formatter for ListFormatter {
+        fn file(&self, file: &PackedFile, index: usize) -> String {
+            format!("{}. {}\n", index, file.display_path)
+        }
+
+        fn separator(&self) -> String {
+            String::new()
+        }
+    }
+
+    #[test]
+    fn test_render_with_custom_formatter() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "a\n").unwrap();
+        fs::write(temp_dir.path().join("b.txt"), "b\n").unwrap();
+
+        let packer = Packer::new()
+            .paths([temp_dir.path().join("a.txt"), temp_dir.path().join("b.txt")])
+            .formatter(ListFormatter);
+
+        assert_eq!(
+            packer.render(&packer.pack().unwrap().files),
+            "1. a.txt\n2. b.txt\n"
+        );
+    }
+}
@@ -0,0 +1,381 @@
+use crate::cli::PackArgs;
+use crate::stats::TextStats;
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([a-z_]+)\s*\}\}").unwrap());
+
+pub fn wraps_output(cli: &PackArgs) -> bool {
+    cli.prompt.is_some()
+        || cli.prompt_file.is_some()
+        || cli.question.is_some()
+        || cli.template.is_some()
+}
+
+#[derive(Default)]
+struct TreeNode(BTreeMap<String, TreeNode>);
+
+fn write_tree(node: &TreeNode, depth: usize, buf: &mut String) {
+    for (name, child) in &node.0 {
+        buf.push_str(&"  ".repeat(depth));
+        buf.push_str(name);
+        if !child.0.is_empty() {
+            buf.push('/');
+        }
+        buf.push('\n');
+        write_tree(child, depth + 1, buf);
+    }
+}
+
+/// The display paths of the files packed from one command-line path
+pub struct RootFiles {
+    /// The path as given on the command line
+    pub root: String,
+    pub paths: Vec<String>,
+}
+
+/// Renders `/`-separated paths as an indented tree sorted by name, starting at `depth`.
+fn render_tree(paths: &[String], depth: usize) -> String {
+    let mut root = TreeNode::default();
+    for path in paths {
+        let mut node = &mut root;
+        for component in path.split('/').filter(|component| !component.is_empty()) {
+            node = node.0.entry(component.to_string()).or_default();
+        }
+    }
+    let mut buf = String::new();
+    write_tree(&root, depth, &mut buf);
+    buf
+}
+
+/// Renders the files of each command-line path as a tree. With several paths, each gets its
+/// own tree under a line naming it, since their display paths are relative to different
+/// directories.
+fn render_trees(roots: &[RootFiles]) -> String {
+    let roots: Vec<&RootFiles> = roots.iter().filter(|root| !root.paths.is_empty()).collect();
+    if let [root] = roots[..] {
+        return render_tree(&root.paths, 0);
+    }
+    let mut buf = String::new();
+    for root in roots {
+        buf.push_str(&root.root);
+        if Path::new(&root.root).is_dir() && !root.root.ends_with('/') {
+            buf.push('/');
+        }
+        buf.push('\n');
+        buf.push_str(&render_tree(&root.paths, 1));
+    }
+    buf
+}
+
+/// Converts days since the Unix epoch to a `(year, month, day)` civil date.
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (year, month, day) = civil_date((seconds / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Branch checked out where the first path lives, or an empty string outside a git repository.
+fn git_branch(cli: &PackArgs) -> String {
+    let Some(path) = cli.paths.first() else {
+        return String::new();
+    };
+    let dir = if path.is_dir() {
+        path.as_path()
+    } else {
+        path.parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+    };
+    Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Substitutes `{{tree}}`, `{{files}}`, `{{stats}}`, `{{date}}` and `{{git_branch}}` in a single
+/// pass, so placeholders inside the bundled files are left alone. Unknown placeholders are kept.
+/// The tree and file count come from `roots`, the display paths of the files in `bundle`.
+fn render_template(
+    cli: &PackArgs,
+    template: &str,
+    bundle: &str,
+    roots: &[RootFiles],
+    stats: TextStats,
+) -> String {
+    let mut values = HashMap::new();
+    for caps in PLACEHOLDER.captures_iter(template) {
+        let name = &caps[1];
+        if values.contains_key(name) {
+            continue;
+        }
+        let value = match name {
+            "files" => bundle.to_string(),
+            "tree" => render_trees(roots),
+            "stats" => format!(
+                "Files: {}, Characters: {}, Words: {}, Non-Empty Lines: {}",
+                roots.iter().map(|root| root.paths.len()).sum::<usize>(),
+                stats.chars,
+                stats.words,
+                stats.lines
+            ),
+            "date" => today(),
+            "git_branch" => git_branch(cli),
+            _ => continue,
+        };
+        values.insert(name.to_string(), value);
+    }
+
+    PLACEHOLDER
+        .replace_all(template, |caps: &Captures| {
+            values
+                .get(&caps[1])
+                .cloned()
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+fn preamble(cli: &PackArgs) -> Result<Option<String>> {
+    match (&cli.prompt, &cli.prompt_file) {
+        (Some(prompt), _) => Ok(Some(prompt.clone())),
+        (None, Some(path)) => fs::read_to_string(path)
+            .map(Some)
+            .with_context(|| format!("Failed to read prompt file {}", path.display())),
+        (None, None) => Ok(None),
+    }
+}
+
+fn prepend_preamble(preamble: &str, body: &str) -> String {
+    format!("{}\n\n{}", preamble.trim_end(), body)
+}
+
+fn append_question(output: &mut String, question: &str) {
+    while !output.is_empty() && !output.ends_with("\n\n") {
+        output.push('\n');
+    }
+    output.push_str(question.trim_end());
+    output.push('\n');
+}
+
+/// Wraps the bundle in the `--template`, with the `--prompt` text before it and the
+/// `--question` after it. `roots` hold the display paths of the files in the bundle.
+pub fn wrap_output(
+    cli: &PackArgs,
+    bundle: String,
+    roots: &[RootFiles],
+    stats: TextStats,
+) -> Result<String> {
+    let body = match &cli.template {
+        Some(path) => {
+            let template = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read template {}", path.display()))?;
+            render_template(cli, &template, &bundle, roots, stats)
+        }
+        None => bundle,
+    };
+
+    let mut output = match preamble(cli)? {
+        Some(preamble) => prepend_preamble(&preamble, &body),
+        None => body,
+    };
+    if let Some(question) = &cli.question {
+        append_question(&mut output, question);
+    }
+
+    Ok(output)
+}
+
+/// Puts the `--prompt` text before the first part and the `--question` after the last one.
+pub fn wrap_parts(cli: &PackArgs, mut parts: Vec<String>) -> Result<Vec<String>> {
+    if let (Some(preamble), Some(first)) = (preamble(cli)?, parts.first_mut()) {
+        *first = prepend_preamble(&preamble, first);
+    }
+    if let (Some(question), Some(last)) = (&cli.question, parts.last_mut()) {
+        append_question(last, question);
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_render_tree() {
+        let paths = vec![
+            "src/main.rs".to_string(),
+            "README.md".to_string(),
+            "src/cli/args.rs".to_string(),
+        ];
+        assert_eq!(
+            render_tree(&paths, 0),
+            "README.md\nsrc/\n  cli/\n    args.rs\n  main.rs\n"
+        );
+    }
+
+    #[test]
+    fn test_render_trees_of_several_roots() {
+        let temp_dir = TempDir::new().unwrap();
+        let app = temp_dir.path().join("app");
+        let lib = temp_dir.path().join("lib");
+        fs::create_dir(&app).unwrap();
+        fs::create_dir(&lib).unwrap();
+        let roots = [
+            RootFiles {
+                root: app.display().to_string(),
+                paths: vec!["src/main.rs".to_string()],
+            },
+            RootFiles {
+                root: lib.display().to_string(),
+                paths: vec!["src/main.rs".to_string()],
+            },
+        ];
+
+        // The same relative path under two roots stays two entries
+        assert_eq!(
+            render_trees(&roots),
+            format!(
+                "{}/\n  src/\n    main.rs\n{}/\n  src/\n    main.rs\n",
+                app.display(),
+                lib.display()
+            )
+        );
+        assert_eq!(render_trees(&roots[..1]), "src/\n  main.rs\n");
+    }
+
+    #[test]
+    fn test_civil_date() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(19_782), (2024, 2, 29));
+        assert_eq!(civil_date(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn test_wrap_output_prompt_and_question() {
+        let cli = PackArgs {
+            prompt: Some("Review this code.\n".to_string()),
+            question: Some("What would you change?".to_string()),
+            ..Default::default()
+        };
+
+        let wrapped = wrap_output(
+            &cli,
+            "**a.rs:**\nfn a() {}\n\n".to_string(),
+            &[RootFiles {
+                root: "a.rs".to_string(),
+                paths: vec!["a.rs".to_string()],
+            }],
+            TextStats::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            wrapped,
+            "Review this code.\n\n**a.rs:**\nfn a() {}\n\nWhat would you change?\n"
+        );
+    }
+
+    #[test]
+    fn test_wrap_parts() {
+        let cli = PackArgs {
+            prompt: Some("Read all parts.".to_string()),
+            question: Some("Done?".to_string()),
+            ..Default::default()
+        };
+
+        let parts = wrap_parts(&cli, vec!["one\n".to_string(), "two\n".to_string()]).unwrap();
+
+        assert_eq!(parts, ["Read all parts.\n\none\n", "two\n\nDone?\n"]);
+    }
+
+    #[test]
+    fn test_wrap_output_prompt_file_missing() {
+        let cli = PackArgs {
+            prompt_file: Some("/nonexistent/prompt.txt".into()),
+            ..Default::default()
+        };
+
+        let result = wrap_output(&cli, String::new(), &[], TextStats::default());
+
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Failed to read prompt file")
+        );
+    }
+
+    #[test]
+    fn test_wrap_output_template() {
+        let temp_dir = TempDir::new().unwrap();
+        let template_path = temp_dir.path().join("template.md");
+        fs::write(
+            &template_path,
+            "Tree:\n{{tree}}\nStats: {{ stats }}\n<code>\n{{files}}</code>\n{{unknown}}\n",
+        )
+        .unwrap();
+
+        let cli = PackArgs {
+            template: Some(template_path),
+            ..Default::default()
+        };
+        let stats = TextStats {
+            chars: 14,
+            words: 3,
+            lines: 1,
+        };
+
+        let wrapped = wrap_output(
+            &cli,
+            "**lib.rs:**\n{{date}}\n\n".to_string(),
+            &[RootFiles {
+                root: "lib.rs".to_string(),
+                paths: vec!["lib.rs".to_string()],
+            }],
+            stats,
+        )
+        .unwrap();
+
+        assert_eq!(
+            wrapped,
+            "Tree:\nlib.rs\n\nStats: Files: 1, Characters: 14, Words: 3, Non-Empty Lines: 1\n<code>\n**lib.rs:**\n{{date}}\n\n</code>\n{{unknown}}\n"
+        );
+    }
+
+    #[test]
+    fn test_today_format() {
+        let date = today();
+        assert_eq!(date.len(), 10);
+        assert_eq!(&date[4..5], "-");
+        assert_eq!(&date[7..8], "-");
+    }
+}
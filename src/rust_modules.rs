@@ -0,0 +1,375 @@
+use crate::comments::{literal_end, strip_comments};
+use crate::language::Language;
+use crate::utils::canonical_path;
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// `mod name;`, or the opening of an inline `mod name {`, with any `#[path]` attribute
+static MOD_DECLARATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?m)^\s*(?:#\[path\s*=\s*"([^"]+)"\]\s*)?(?:pub(?:\s*\([^)]*\))?\s+)?mod\s+(?:r#)?([A-Za-z_][A-Za-z0-9_]*)\s*([;{])"#,
+    )
+    .unwrap()
+});
+
+const CRATE_ROOT_DIRS: [&str; 4] = ["bin", "examples", "tests", "benches"];
+
+struct ModDeclaration {
+    name: String,
+    path_attribute: Option<String>,
+    /// Directories of the inline modules the declaration is inside, outermost first
+    inline_dirs: Vec<String>,
+}
+
+/// Finds the `mod name;` declarations in `source`, noting the inline `mod name { ... }` blocks
+/// each one is nested in. Braces and declarations inside string literals are ignored.
+fn mod_declarations(source: &str) -> Vec<ModDeclaration> {
+    let source = strip_comments(source, Language::Rust);
+    let mut declarations = Vec::new();
+    let mut matches = MOD_DECLARATION.captures_iter(&source).peekable();
+    // The inline modules open at `i`, each with the brace depth just inside it
+    let mut inline: Vec<(String, usize)> = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < source.len() {
+        while matches
+            .next_if(|caps| caps.get(3).unwrap().start() < i)
+            .is_some()
+        {}
+        if let Some(caps) = matches.next_if(|caps| caps.get(3).unwrap().start() == i) {
+            let path_attribute = caps.get(1).map(|m| m.as_str().to_string());
+            if &caps[3] == "{" {
+                depth += 1;
+                inline.push((path_attribute.unwrap_or_else(|| caps[2].to_string()), depth));
+            } else {
+                declarations.push(ModDeclaration {
+                    name: caps[2].to_string(),
+                    path_attribute,
+                    inline_dirs: inline.iter().map(|(dir, _)| dir.clone()).collect(),
+                });
+            }
+            i += 1;
+            continue;
+        }
+        match source.as_bytes()[i] {
+            b'{' => depth += 1,
+            b'}' => {
+                if inline.last().is_some_and(|&(_, open)| open == depth) {
+                    inline.pop();
+                }
+                depth = depth.saturating_sub(1);
+            }
+            _ => {
+                if let Some(end) = literal_end(&source, i, Language::Rust) {
+                    i = end;
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+    declarations
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+
+/// Whether an entry file is a crate root, whose child modules live next to it.
+fn is_crate_root(path: &Path) -> bool {
+    matches!(
+        file_name(path),
+        "main.rs" | "lib.rs" | "mod.rs" | "build.rs"
+    ) || path
+        .parent()
+        .is_some_and(|parent| CRATE_ROOT_DIRS.contains(&file_name(parent)))
+}
+
+/// Directory holding the files of modules declared in `file`.
+fn child_module_dir(file: &Path, is_root: bool) -> PathBuf {
+    let dir = file.parent().unwrap_or(Path::new("."));
+    if is_root || file_name(file) == "mod.rs" {
+        dir.to_path_buf()
+    } else {
+        dir.join(file.file_stem().unwrap_or_default())
+    }
+}
+
+/// The file of a declared module, or the path it was expected at when there is none.
+fn resolve_module(
+    file: &Path,
+    is_root: bool,
+    declaration: &ModDeclaration,
+) -> Result<PathBuf, PathBuf> {
+    let mut dir = child_module_dir(file, is_root);
+    dir.extend(&declaration.inline_dirs);
+    if let Some(path_attribute) = &declaration.path_attribute {
+        // Outside inline modules, `#[path]` is relative to the declaring file
+        let base = if declaration.inline_dirs.is_empty() {
+            file.parent().unwrap_or(Path::new("."))
+        } else {
+            &dir
+        };
+        let resolved = base.join(path_attribute);
+        return if resolved.is_file() {
+            Ok(resolved)
+        } else {
+            Err(resolved)
+        };
+    }
+    let candidates = [
+        dir.join(format!("{}.rs", declaration.name)),
+        dir.join(&declaration.name).join("mod.rs"),
+    ];
+    match candidates.iter().find(|candidate| candidate.is_file()) {
+        Some(found) => Ok(found.clone()),
+        None => Err(candidates[0].clone()),
+    }
+}
+
+/// Normalizes `entry` so that it has a non-empty parent directory, which is returned too.
+fn entry_with_dir(entry: &Path) -> (PathBuf, PathBuf) {
+    let dir = entry
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .to_path_buf();
+    (dir.join(entry.file_name().unwrap_or_default()), dir)
+}
+
+/// The files of a module tree, and the modules whose files are missing
+#[derive(Default)]
+pub struct ModuleTree {
+    pub files: Vec<PathBuf>,
+    /// Where each missing module file was expected, with the error to report for it
+    pub missing: Vec<(PathBuf, String)>,
+}
+
+fn visit(
+    file: &Path,
+    source: &str,
+    is_root: bool,
+    visited: &mut HashSet<PathBuf>,
+    tree: &mut ModuleTree,
+) {
+    for declaration in mod_declarations(source) {
+        match resolve_module(file, is_root, &declaration) {
+            Ok(child) => {
+                if !visited.insert(canonical_path(&child)) {
+                    continue;
+                }
+                tree.files.push(child.clone());
+                // A module file that cannot be read is packed with its error
+                if let Ok(source) = fs::read_to_string(&child) {
+                    visit(&child, &source, false, visited, tree);
+                }
+            }
+            Err(expected) => tree.missing.push((
+                expected,
+                format!(
+                    "Could not find file for module '{}' declared in {}",
+                    declaration.name,
+                    file.display()
+                ),
+            )),
+        }
+    }
+}
+
+/// Collects `entry` and every file reachable through its `mod foo;` declarations, in
+/// declaration order. Declarations inside inline `mod foo { ... }` blocks resolve relative to
+/// the inline module's directory, such as `foo/bar.rs` for `mod foo { mod bar; }` in a crate
+/// root.
+pub fn collect_module_tree(entry: &Path) -> Result<ModuleTree> {
+    let source = fs::read_to_string(entry)
+        .with_context(|| format!("Failed to read file {}", entry.display()))?;
+    let mut tree = ModuleTree {
+        files: vec![entry.to_path_buf()],
+        missing: Vec::new(),
+    };
+    let mut visited = HashSet::from([canonical_path(entry)]);
+    visit(
+        entry,
+        &source,
+        is_crate_root(entry),
+        &mut visited,
+        &mut tree,
+    );
+    Ok(tree)
+}
+
+/// The module tree of `entry`, with the directory its paths are shown relative to.
+pub fn module_tree_selection(entry: &Path) -> Result<(ModuleTree, PathBuf)> {
+    let (entry, dir) = entry_with_dir(entry);
+    let tree = collect_module_tree(&entry)?;
+    Ok((tree, dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::PackArgs;
+    use crate::directory_operations::join_blocks;
+    use crate::formatter::DefaultFormatter;
+    use crate::utils::pack_path;
+    use tempfile::TempDir;
+
+    fn names(files: &[PathBuf], root: &Path) -> Vec<String> {
+        files
+            .iter()
+            .map(|file| file.strip_prefix(root).unwrap().display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_mod_declarations() {
+        let source = "mod cli;\npub mod utils;\npub(crate) mod ops;\n// mod commented;\n#[cfg(test)]\nmod tests;\n#[path = \"other/x.rs\"]\nmod renamed;\nmod inline { }\n";
+        let declarations = mod_declarations(source);
+        let names: Vec<&str> = declarations.iter().map(|d| d.name.as_str()).collect();
+
+        assert_eq!(names, ["cli", "utils", "ops", "tests", "renamed"]);
+        assert_eq!(
+            declarations[4].path_attribute.as_deref(),
+            Some("other/x.rs")
+        );
+    }
+
+    #[test]
+    fn test_mod_declarations_inside_inline_modules() {
+        let source = "mod outer {\n    fn f() { let s = \"}\"; }\n    mod nested;\n    #[path = \"x\"]\n    mod deeper {\n        mod leaf;\n    }\n}\nmod top;\n";
+        let declarations = mod_declarations(source);
+        let found: Vec<(&str, Vec<String>)> = declarations
+            .iter()
+            .map(|d| (d.name.as_str(), d.inline_dirs.clone()))
+            .collect();
+
+        assert_eq!(
+            found,
+            [
+                ("nested", vec!["outer".to_string()]),
+                ("leaf", vec!["outer".to_string(), "x".to_string()]),
+                ("top", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_module_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src");
+        fs::create_dir_all(src.join("net")).unwrap();
+        fs::create_dir_all(src.join("cli")).unwrap();
+        fs::write(src.join("main.rs"), "mod cli;\nmod net;\nfn main() {}\n").unwrap();
+        fs::write(src.join("cli.rs"), "mod args;\n").unwrap();
+        fs::write(src.join("cli").join("args.rs"), "").unwrap();
+        fs::write(src.join("net").join("mod.rs"), "mod tcp;\n").unwrap();
+        fs::write(src.join("net").join("tcp.rs"), "").unwrap();
+        fs::write(src.join("unused.rs"), "").unwrap();
+
+        let files = collect_module_tree(&src.join("main.rs")).unwrap().files;
+
+        assert_eq!(
+            names(&files, &src),
+            [
+                "main.rs",
+                "cli.rs",
+                "cli/args.rs",
+                "net/mod.rs",
+                "net/tcp.rs"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_module_tree_path_attribute_and_missing() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path();
+        fs::create_dir(src.join("gen")).unwrap();
+        fs::write(
+            src.join("lib.rs"),
+            "#[path = \"gen/bindings.rs\"]\nmod bindings;\nmod missing;\n",
+        )
+        .unwrap();
+        fs::write(src.join("gen").join("bindings.rs"), "").unwrap();
+
+        let files = collect_module_tree(&src.join("lib.rs")).unwrap().files;
+
+        assert_eq!(names(&files, src), ["lib.rs", "gen/bindings.rs"]);
+    }
+
+    #[test]
+    fn test_collect_module_tree_inline_module() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src");
+        fs::create_dir_all(src.join("outer")).unwrap();
+        fs::write(src.join("lib.rs"), "mod outer {\n    mod nested;\n}\n").unwrap();
+        fs::write(src.join("outer").join("nested.rs"), "").unwrap();
+        // A file of the same name next to lib.rs is not the module
+        fs::write(src.join("nested.rs"), "").unwrap();
+
+        let files = collect_module_tree(&src.join("lib.rs")).unwrap().files;
+
+        assert_eq!(names(&files, &src), ["lib.rs", "outer/nested.rs"]);
+    }
+
+    #[test]
+    fn test_collect_module_tree_cycle() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path();
+        fs::write(src.join("main.rs"), "#[path = \"main.rs\"]\nmod again;\n").unwrap();
+
+        let files = collect_module_tree(&src.join("main.rs")).unwrap().files;
+
+        assert_eq!(names(&files, src), ["main.rs"]);
+    }
+
+    #[test]
+    fn test_process_path_follow_mods() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("main.rs"), "mod cli;\n").unwrap();
+        fs::write(temp_dir.path().join("cli.rs"), "pub struct Cli;\n").unwrap();
+        fs::write(temp_dir.path().join("stray.rs"), "// not declared\n").unwrap();
+
+        let cli = PackArgs {
+            follow_mods: true,
+            ..Default::default()
+        };
+
+        let packed = pack_path(&cli, &temp_dir.path().join("main.rs")).unwrap();
+        let formatter = DefaultFormatter::from_args(&cli);
+        let content = join_blocks(&formatter, &packed.blocks(&cli, &formatter, &mut 0));
+
+        assert!(content.contains("**main.rs:**"));
+        assert!(content.contains("**cli.rs:**\npub struct Cli;"));
+        assert!(!content.contains("stray.rs"));
+    }
+
+    #[test]
+    fn test_process_path_follow_mods_reports_missing_modules() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("main.rs"), "mod gone;\n").unwrap();
+
+        let cli = PackArgs {
+            follow_mods: true,
+            include_errors: true,
+            ..Default::default()
+        };
+
+        let packed = pack_path(&cli, &temp_dir.path().join("main.rs")).unwrap();
+        let formatter = DefaultFormatter::from_args(&cli);
+        let content = join_blocks(&formatter, &packed.blocks(&cli, &formatter, &mut 0));
+
+        assert!(
+            content.contains(
+                "**gone.rs:** [error: Could not find file for module 'gone' declared in "
+            )
+        );
+    }
+}
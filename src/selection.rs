@@ -0,0 +1,246 @@
+use crate::cli::{OversizePolicy, PackArgs};
+use crate::encoding::{read_text, sniff_utf16};
+use crate::redaction::is_sensitive_file;
+use anyhow::{Context, Result};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const SNIFF_LEN: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    Binary,
+    TooLarge,
+    Sensitive,
+    NoMatch,
+    BrokenSymlink,
+    SymlinkOutsideRoot,
+    SymlinkLoop,
+    DuplicateDirectory,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Binary => write!(f, "binary"),
+            SkipReason::TooLarge => write!(f, "too large"),
+            SkipReason::Sensitive => {
+                write!(f, "may contain secrets, name it explicitly to include")
+            }
+            SkipReason::NoMatch => write!(f, "no match for --grep"),
+            SkipReason::BrokenSymlink => write!(f, "broken symlink"),
+            SkipReason::SymlinkOutsideRoot => write!(f, "symlink outside the input directory"),
+            SkipReason::SymlinkLoop => write!(f, "symlink loop"),
+            SkipReason::DuplicateDirectory => write!(f, "directory already included"),
+        }
+    }
+}
+
+/// A file the selection left out, or omitted for its size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub display_path: String,
+    pub reason: SkipReason,
+}
+
+/// Whether a file looks binary: it has a zero byte near the start and is not UTF-16. This is
+/// sniffed from the file itself, so `--encoding utf-16le` does not let binary files through.
+fn is_binary(path: &Path) -> Result<bool> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to read file {}", path.display()))?;
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    file.by_ref()
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)
+        .with_context(|| format!("Failed to read file {}", path.display()))?;
+    Ok(head.contains(&0) && sniff_utf16(&head).is_none())
+}
+
+/// The notice that `path` was skipped, for `--include-errors` to show on stderr
+pub fn skip_notice(path: &Path, reason: SkipReason) -> String {
+    format!("SKIPPED: {} ({})", path.display(), reason)
+}
+
+/// Decides whether a file should be skipped, reading no more than needed to sniff its type.
+/// Files named `explicit`ly on the command line are never refused as sensitive.
+pub fn skip_reason(cli: &PackArgs, path: &Path, explicit: bool) -> Result<Option<SkipReason>> {
+    if !explicit && is_sensitive_file(path) {
+        return Ok(Some(SkipReason::Sensitive));
+    }
+    if let Some(max_file_size) = cli.max_file_size
+        && cli.oversize == OversizePolicy::Skip
+    {
+        let size = fs::metadata(path)
+            .with_context(|| format!("Failed to read metadata of {}", path.display()))?
+            .len();
+        if size > max_file_size {
+            return Ok(Some(SkipReason::TooLarge));
+        }
+    }
+    if is_binary(path)? {
+        return Ok(Some(SkipReason::Binary));
+    }
+    if let Some(pattern) = &cli.grep {
+        let contents = read_text(path, cli.encoding, true)?;
+        if !pattern.is_match(&contents) {
+            return Ok(Some(SkipReason::NoMatch));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::Encoding;
+    use crate::grep::parse_grep_pattern;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_skip_reason_text_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "Hello World\n").unwrap();
+
+        assert_eq!(
+            skip_reason(&PackArgs::default(), &file_path, false).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_skip_reason_binary_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("image.png");
+        fs::write(&file_path, [0x89, b'P', b'N', b'G', 0x00, 0x01]).unwrap();
+
+        assert_eq!(
+            skip_reason(&PackArgs::default(), &file_path, false).unwrap(),
+            Some(SkipReason::Binary)
+        );
+    }
+
+    #[test]
+    fn test_skip_reason_binary_file_with_utf16_encoding() {
+        let temp_dir = TempDir::new().unwrap();
+        let binary_path = temp_dir.path().join("image.png");
+        fs::write(&binary_path, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        let text_path = temp_dir.path().join("notes.txt");
+        let text: Vec<u8> = "notes\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        fs::write(&text_path, text).unwrap();
+
+        let cli = PackArgs {
+            encoding: Some(Encoding::Utf16Le),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            skip_reason(&cli, &binary_path, false).unwrap(),
+            Some(SkipReason::Binary)
+        );
+        assert_eq!(skip_reason(&cli, &text_path, false).unwrap(), None);
+    }
+
+    #[test]
+    fn test_skip_reason_grep_no_match() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("notes.txt");
+        fs::write(&file_path, "Hello World\n").unwrap();
+
+        let matching = PackArgs {
+            grep: Some(parse_grep_pattern("W.rld").unwrap()),
+            ..Default::default()
+        };
+        let missing = PackArgs {
+            grep: Some(parse_grep_pattern("copy_to_clipboard").unwrap()),
+            ..Default::default()
+        };
+
+        assert_eq!(skip_reason(&matching, &file_path, true).unwrap(), None);
+        assert_eq!(
+            skip_reason(&missing, &file_path, true).unwrap(),
+            Some(SkipReason::NoMatch)
+        );
+    }
+
+    #[test]
+    fn test_skip_reason_empty_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("empty.txt");
+        fs::write(&file_path, "").unwrap();
+
+        assert_eq!(
+            skip_reason(&PackArgs::default(), &file_path, false).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_skip_reason_nonexistent() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("nonexistent.txt");
+
+        assert!(skip_reason(&PackArgs::default(), &file_path, false).is_err());
+    }
+
+    #[test]
+    fn test_skip_reason_too_large() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("schema.json");
+        fs::write(&file_path, "x".repeat(100)).unwrap();
+
+        let cli = PackArgs {
+            max_file_size: Some(10),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            skip_reason(&cli, &file_path, false).unwrap(),
+            Some(SkipReason::TooLarge)
+        );
+    }
+
+    #[test]
+    fn test_skip_reason_too_large_truncate_policy() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("schema.json");
+        fs::write(&file_path, "x".repeat(100)).unwrap();
+
+        let cli = PackArgs {
+            max_file_size: Some(10),
+            oversize: OversizePolicy::Truncate,
+            ..Default::default()
+        };
+
+        assert_eq!(skip_reason(&cli, &file_path, false).unwrap(), None);
+    }
+
+    #[test]
+    fn test_skip_reason_sensitive() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".env");
+        fs::write(&file_path, "API_TOKEN=abc\n").unwrap();
+
+        assert_eq!(
+            skip_reason(&PackArgs::default(), &file_path, false).unwrap(),
+            Some(SkipReason::Sensitive)
+        );
+        assert_eq!(
+            skip_reason(&PackArgs::default(), &file_path, true).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_skip_reason_display() {
+        assert_eq!(SkipReason::Binary.to_string(), "binary");
+        assert_eq!(SkipReason::TooLarge.to_string(), "too large");
+    }
+}
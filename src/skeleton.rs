@@ -0,0 +1,346 @@
+use crate::comments::{comment_end, literal_end};
+use crate::language::Language;
+use regex::Regex;
+use std::sync::LazyLock;
+
+const ELIDED_BODY: &str = "{ ... }";
+
+static RUST_FN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bfn\b").unwrap());
+static GO_FUNC: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bfunc\b").unwrap());
+static JS_FUNCTION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bfunction\b|=>\s*$|\)\s*(:[^=;{}]*)?$").unwrap());
+static JS_CONTROL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(\}\s*)?(if|else|for|while|do|switch|catch|with|try|finally)\b").unwrap()
+});
+
+/// Whether the `{` following `prefix` (the statement text before it) opens a function body.
+fn opens_function_body(prefix: &str, language: Language) -> bool {
+    match language {
+        Language::Rust => RUST_FN.is_match(prefix),
+        Language::Go => GO_FUNC.is_match(prefix),
+        Language::JavaScript | Language::TypeScript => {
+            JS_FUNCTION.is_match(prefix.trim_end()) && !JS_CONTROL.is_match(prefix)
+        }
+        _ => false,
+    }
+}
+
+fn matching_brace_end(contents: &str, open: usize, language: Language) -> usize {
+    let bytes = contents.as_bytes();
+    let mut depth = 0;
+    let mut i = open;
+    while i < bytes.len() {
+        if let Some(end) =
+            comment_end(contents, i, language).or_else(|| literal_end(contents, i, language))
+        {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+fn brace_skeleton(contents: &str, language: Language) -> String {
+    let bytes = contents.as_bytes();
+    let mut skeleton = String::with_capacity(contents.len());
+    let mut copied_up_to = 0;
+    let mut statement_start = 0;
+    let mut nesting: usize = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if let Some(end) = comment_end(contents, i, language) {
+            // Comments never decide what a brace opens, so the statement starts after them
+            i = end;
+            if nesting == 0 {
+                statement_start = end;
+            }
+            continue;
+        }
+        if let Some(end) = literal_end(contents, i, language) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'(' | b'[' => nesting += 1,
+            b')' | b']' => nesting = nesting.saturating_sub(1),
+            b';' | b'}' if nesting == 0 => statement_start = i + 1,
+            b'{' if nesting == 0 => {
+                if opens_function_body(&contents[statement_start..i], language) {
+                    skeleton.push_str(&contents[copied_up_to..i]);
+                    skeleton.push_str(ELIDED_BODY);
+                    i = matching_brace_end(contents, i, language);
+                    copied_up_to = i;
+                    statement_start = i;
+                    continue;
+                }
+                statement_start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    skeleton.push_str(&contents[copied_up_to..]);
+    skeleton
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_python_def(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("def ") || trimmed.starts_with("async def ")
+}
+
+fn docstring_quote(line: &str) -> Option<&'static str> {
+    let trimmed = line
+        .trim_start()
+        .trim_start_matches(['r', 'R', 'u', 'U', 'b', 'B']);
+    ["\"\"\"", "'''", "\"", "'"]
+        .into_iter()
+        .find(|quote| trimmed.starts_with(quote))
+}
+
+/// Index of the last line of a signature starting at `start`, i.e. where brackets balance.
+fn python_signature_end(lines: &[&str], start: usize) -> usize {
+    let mut depth: i32 = 0;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        for byte in line.bytes() {
+            match byte {
+                b'(' | b'[' | b'{' => depth += 1,
+                b')' | b']' | b'}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if depth <= 0 {
+            return index;
+        }
+    }
+    lines.len() - 1
+}
+
+/// Index just past the docstring starting on `lines[start]`.
+fn python_docstring_end(lines: &[&str], start: usize, quote: &str) -> usize {
+    let first = lines[start].trim();
+    let opening = first.find(quote).unwrap_or(0) + quote.len();
+    if first[opening..].contains(quote) {
+        return start + 1;
+    }
+    lines
+        .iter()
+        .enumerate()
+        .skip(start + 1)
+        .find(|(_, line)| line.contains(quote))
+        .map_or(lines.len(), |(index, _)| index + 1)
+}
+
+fn python_skeleton(contents: &str) -> String {
+    let lines: Vec<&str> = contents.split_inclusive('\n').collect();
+    let mut skeleton = String::with_capacity(contents.len());
+    let mut i = 0;
+
+    while i < lines.len() {
+        if !is_python_def(lines[i]) {
+            skeleton.push_str(lines[i]);
+            i += 1;
+            continue;
+        }
+
+        let def_indent = indent_of(lines[i]);
+        let signature_end = python_signature_end(&lines, i);
+        lines[i..=signature_end]
+            .iter()
+            .for_each(|line| skeleton.push_str(line));
+
+        let mut body_end = signature_end + 1;
+        let mut last_code = signature_end;
+        while body_end < lines.len()
+            && (lines[body_end].trim().is_empty() || indent_of(lines[body_end]) > def_indent)
+        {
+            if !lines[body_end].trim().is_empty() {
+                last_code = body_end;
+            }
+            body_end += 1;
+        }
+
+        let first_body = (signature_end + 1..=last_code).find(|&k| !lines[k].trim().is_empty());
+        if let Some(first_body) = first_body {
+            let mut kept_up_to = first_body;
+            if let Some(quote) = docstring_quote(lines[first_body]) {
+                kept_up_to = python_docstring_end(&lines, first_body, quote).min(last_code + 1);
+                lines[first_body..kept_up_to]
+                    .iter()
+                    .for_each(|line| skeleton.push_str(line));
+            }
+            if kept_up_to <= last_code {
+                let body_indent = &lines[first_body][..indent_of(lines[first_body])];
+                skeleton.push_str(body_indent);
+                skeleton.push_str("...\n");
+            }
+        }
+
+        i = last_code + 1;
+    }
+
+    skeleton
+}
+
+/// Reduces source code to its outline: types, signatures and doc comments stay, while
+/// function bodies become `{ ... }` (or `...` in Python). Unsupported languages are unchanged.
+pub fn skeletonize(contents: &str, language: Language) -> String {
+    match language {
+        Language::Rust | Language::Go | Language::JavaScript | Language::TypeScript => {
+            brace_skeleton(contents, language)
+        }
+        Language::Python => python_skeleton(contents),
+        _ => contents.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_skeleton() {
+        let source = r#"/// A point.
+#[derive(Debug)]
+pub struct Point {
+    x: i32,
+}
+
+impl Point {
+    /// Creates a point.
+    pub fn new(x: i32) -> Self {
+        let s = "}";
+        Point { x }
+    }
+}
+
+pub trait Shape {
+    fn area(&self) -> f64;
+    fn name(&self) -> &str {
+        "shape"
+    }
+}
+"#;
+        let expected = r#"/// A point.
+#[derive(Debug)]
+pub struct Point {
+    x: i32,
+}
+
+impl Point {
+    /// Creates a point.
+    pub fn new(x: i32) -> Self { ... }
+}
+
+pub trait Shape {
+    fn area(&self) -> f64;
+    fn name(&self) -> &str { ... }
+}
+"#;
+        assert_eq!(skeletonize(source, Language::Rust), expected);
+    }
+
+    #[test]
+    fn test_rust_skeleton_where_clause_and_fn_pointer() {
+        let source = "fn apply<F>(f: fn(i32) -> i32, g: F) -> i32\nwhere\n    F: Fn(),\n{\n    f(1)\n}\nstruct S { f: fn() }\n";
+        assert_eq!(
+            skeletonize(source, Language::Rust),
+            "fn apply<F>(f: fn(i32) -> i32, g: F) -> i32\nwhere\n    F: Fn(),\n{ ... }\nstruct S { f: fn() }\n"
+        );
+    }
+
+    #[test]
+    fn test_go_skeleton() {
+        let source = "type Server struct {\n\tAddr string\n}\n\n// Start runs it.\nfunc (s *Server) Start() error {\n\treturn nil\n}\n";
+        assert_eq!(
+            skeletonize(source, Language::Go),
+            "type Server struct {\n\tAddr string\n}\n\n// Start runs it.\nfunc (s *Server) Start() error { ... }\n"
+        );
+    }
+
+    #[test]
+    fn test_typescript_skeleton() {
+        let source = "interface User {\n  name: string;\n}\n\nexport class Repo {\n  constructor(private db: Db) {\n    this.db = db;\n  }\n\n  find(id: number): User | undefined {\n    if (id) { return undefined; }\n  }\n}\n\nexport const handler = async (req: Request) => {\n  return `${req}`;\n};\n";
+        assert_eq!(
+            skeletonize(source, Language::TypeScript),
+            "interface User {\n  name: string;\n}\n\nexport class Repo {\n  constructor(private db: Db) { ... }\n\n  find(id: number): User | undefined { ... }\n}\n\nexport const handler = async (req: Request) => { ... };\n"
+        );
+    }
+
+    #[test]
+    fn test_python_skeleton() {
+        let source = r#"import os
+
+
+class Store:
+    """A store."""
+
+    def __init__(self, path):
+        self.path = path
+
+    async def load(
+        self, key: str
+    ) -> bytes:
+        """Load a key.
+
+        Returns bytes.
+        """
+        with open(self.path) as f:
+            return f.read()
+
+
+def main():
+    Store("x")
+"#;
+        let expected = r#"import os
+
+
+class Store:
+    """A store."""
+
+    def __init__(self, path):
+        ...
+
+    async def load(
+        self, key: str
+    ) -> bytes:
+        """Load a key.
+
+        Returns bytes.
+        """
+        ...
+
+
+def main():
+    ...
+"#;
+        assert_eq!(skeletonize(source, Language::Python), expected);
+    }
+
+    #[test]
+    fn test_python_skeleton_docstring_only_body() {
+        let source = "def f():\n    \"\"\"Only a docstring.\"\"\"\n";
+        assert_eq!(skeletonize(source, Language::Python), source);
+    }
+
+    #[test]
+    fn test_unsupported_language_unchanged() {
+        let source = "[package]\nname = \"x\"\n";
+        assert_eq!(skeletonize(source, Language::Toml), source);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::directory_operations::FileBlock;
+use crate::formatter::Formatter;
+use std::path::{Path, PathBuf};
+
+/// Rough size of a token for `--split-at` token limits
+const CHARS_PER_TOKEN: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitUnit {
+    Chars,
+    Tokens,
+}
+
+/// Maximum size of each part written by `--split-at`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SplitLimit {
+    pub amount: usize,
+    pub unit: SplitUnit,
+}
+
+impl SplitLimit {
+    fn max_chars(self) -> usize {
+        match self.unit {
+            SplitUnit::Chars => self.amount,
+            SplitUnit::Tokens => self.amount.saturating_mul(CHARS_PER_TOKEN),
+        }
+    }
+}
+
+/// Parses a limit such as `30000`, `30000chars` or `8000tokens` (also `c`/`t`).
+pub fn parse_split_limit(value: &str) -> Result<SplitLimit, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+    let amount: usize = digits
+        .parse()
+        .map_err(|_| format!("invalid split size '{}'", value))?;
+    if amount == 0 {
+        return Err("split size must be greater than zero".to_string());
+    }
+    let unit = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "c" | "chars" => SplitUnit::Chars,
+        "t" | "tokens" => SplitUnit::Tokens,
+        other => return Err(format!("unknown split unit '{}'", other)),
+    };
+    Ok(SplitLimit { amount, unit })
+}
+
+/// Breaks a block that alone exceeds `max_chars` into pieces, at line boundaries where possible.
+fn split_content(content: &str, max_chars: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    let mut piece_chars = 0;
+
+    for line in content.split_inclusive('\n') {
+        let line_chars = line.chars().count();
+        if piece_chars + line_chars > max_chars && !piece.is_empty() {
+            pieces.push(std::mem::take(&mut piece));
+            piece_chars = 0;
+        }
+        if line_chars > max_chars {
+            let chars: Vec<char> = line.chars().collect();
+            for chunk in chars.chunks(max_chars) {
+                pieces.push(chunk.iter().collect());
+            }
+            continue;
+        }
+        piece.push_str(line);
+        piece_chars += line_chars;
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+fn part_header(index: usize, count: usize, names: &[String]) -> String {
+    let mut header = format!("**Part {} of {}**\nContains:\n", index, count);
+    for name in names {
+        header.push_str(&format!("- {}\n", name));
+    }
+    header.push('\n');
+    header
+}
+
+/// Groups file blocks into parts of at most `limit` (not counting each part's header), never
+/// splitting a file unless it alone exceeds the limit. Each part starts with a "Part i of n"
+/// header listing the files it contains; the formatter's start and end go in the first and
+/// last parts.
+pub fn split_into_parts(
+    formatter: &dyn Formatter,
+    blocks: &[FileBlock],
+    limit: SplitLimit,
+) -> Vec<String> {
+    let max_chars = limit.max_chars();
+    let separator = formatter.separator();
+    let separator_chars = separator.chars().count();
+
+    let mut parts: Vec<(Vec<String>, String)> = Vec::new();
+    let mut names = Vec::new();
+    let mut body = String::new();
+    let mut body_chars = 0;
+
+    for block in blocks {
+        let content_chars = block.content.chars().count();
+        let pieces = if content_chars > max_chars {
+            split_content(&block.content, max_chars)
+        } else {
+            vec![block.content.clone()]
+        };
+
+        for (index, piece) in pieces.into_iter().enumerate() {
+            let piece_chars = piece.chars().count();
+            if !body.is_empty() && body_chars + separator_chars + piece_chars > max_chars {
+                parts.push((std::mem::take(&mut names), std::mem::take(&mut body)));
+                body_chars = 0;
+            }
+            if !body.is_empty() {
+                body.push_str(&separator);
+                body_chars += separator_chars;
+            }
+            body.push_str(&piece);
+            body_chars += piece_chars;
+            names.push(if index == 0 {
+                block.display_path.clone()
+            } else {
+                format!("{} (continued)", block.display_path)
+            });
+        }
+    }
+    if !body.is_empty() {
+        parts.push((names, body));
+    }
+
+    let count = parts.len();
+    if let Some((_, first)) = parts.first_mut() {
+        first.insert_str(0, &formatter.start());
+    }
+    if let Some((_, last)) = parts.last_mut() {
+        last.push_str(&formatter.end());
+    }
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(index, (names, body))| format!("{}{}", part_header(index + 1, count, &names), body))
+        .collect()
+}
+
+/// Path of the `index`th part, e.g. `context.part1.md` for `context.md`.
+pub fn part_path(output: &Path, index: usize) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = match output.extension() {
+        Some(extension) => format!("{}.part{}.{}", stem, index, extension.to_string_lossy()),
+        None => format!("{}.part{}", stem, index),
+    };
+    output.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatter::DefaultFormatter;
+
+    fn block(path: &str, content: &str) -> FileBlock {
+        FileBlock {
+            display_path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_split_limit() {
+        assert_eq!(
+            parse_split_limit("30000").unwrap(),
+            SplitLimit {
+                amount: 30000,
+                unit: SplitUnit::Chars
+            }
+        );
+        assert_eq!(
+            parse_split_limit("8000tokens").unwrap(),
+            SplitLimit {
+                amount: 8000,
+                unit: SplitUnit::Tokens
+            }
+        );
+        assert_eq!(parse_split_limit("500c").unwrap().unit, SplitUnit::Chars);
+        assert!(parse_split_limit("0").is_err());
+        assert!(parse_split_limit("10words").is_err());
+        assert!(parse_split_limit("tokens").is_err());
+    }
+
+    #[test]
+    fn test_split_into_parts_keeps_files_whole() {
+        let blocks = vec![
+            block("a.rs", "**a.rs:**\naaaa\n\n"),
+            block("b.rs", "**b.rs:**\nbbbb\n\n"),
+            block("c.rs", "**c.rs:**\ncccc\n\n"),
+        ];
+        let limit = parse_split_limit("45").unwrap();
+
+        let parts = split_into_parts(&DefaultFormatter::default(), &blocks, limit);
+
+        assert_eq!(
+            parts,
+            [
+                "**Part 1 of 2**\nContains:\n- a.rs\n- b.rs\n\n**a.rs:**\naaaa\n\n----------\n**b.rs:**\nbbbb\n\n",
+                "**Part 2 of 2**\nContains:\n- c.rs\n\n**c.rs:**\ncccc\n\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_into_parts_splits_oversized_file() {
+        let blocks = vec![block("big.txt", "**big.txt:**\nline 1\nline 2\nline 3\n\n")];
+        let limit = parse_split_limit("5t").unwrap();
+
+        let parts = split_into_parts(&DefaultFormatter::default(), &blocks, limit);
+
+        assert_eq!(
+            parts,
+            [
+                "**Part 1 of 2**\nContains:\n- big.txt\n\n**big.txt:**\nline 1\n",
+                "**Part 2 of 2**\nContains:\n- big.txt (continued)\n\nline 2\nline 3\n\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_content_long_line() {
+        assert_eq!(
+            split_content("abcdefgh\nij\n", 3),
+            ["abc", "def", "gh\n", "ij\n"]
+        );
+    }
+
+    #[test]
+    fn test_part_path() {
+        assert_eq!(
+            part_path(Path::new("out/context.md"), 2),
+            PathBuf::from("out/context.part2.md")
+        );
+        assert_eq!(
+            part_path(Path::new("context"), 1),
+            PathBuf::from("context.part1")
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::iter::Sum;
+use std::ops::{Add, AddAssign};
+
+/// Character, word and non-empty line counts of some output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TextStats {
+    pub chars: usize,
+    pub words: usize,
+    /// Number of lines with anything besides whitespace
+    pub lines: usize,
+}
+
+impl TextStats {
+    pub fn of(text: &str) -> Self {
+        Self {
+            chars: text.chars().count(),
+            words: text.split_whitespace().count(),
+            lines: text.lines().filter(|line| !line.trim().is_empty()).count(),
+        }
+    }
+}
+
+impl Add for TextStats {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            chars: self.chars + other.chars,
+            words: self.words + other.words,
+            lines: self.lines + other.lines,
+        }
+    }
+}
+
+impl AddAssign for TextStats {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Sum for TextStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_stats_of() {
+        assert_eq!(
+            TextStats::of("Hello World\n\n  \ncafé\n"),
+            TextStats {
+                chars: 21,
+                words: 3,
+                lines: 2
+            }
+        );
+        assert_eq!(TextStats::of(""), TextStats::default());
+    }
+
+    #[test]
+    fn test_text_stats_sum() {
+        let total: TextStats = [TextStats::of("a b\n"), TextStats::of("c\n")]
+            .into_iter()
+            .sum();
+
+        assert_eq!(
+            total,
+            TextStats {
+                chars: 6,
+                words: 3,
+                lines: 2
+            }
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+static VARIABLE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{([a-z_]+)\}").unwrap());
+
+/// Values available to `--header-template` and `--footer-template`
+pub struct FileVariables<'a> {
+    pub path: &'a str,
+    pub abs_path: &'a str,
+    pub lang: &'a str,
+    pub lines: usize,
+    pub size: usize,
+    pub index: usize,
+    pub note: &'a str,
+}
+
+/// Parses a template given on the command line, turning `\n`, `\t` and `\\` into the
+/// characters they stand for.
+pub fn parse_template(value: &str) -> Result<String, String> {
+    let mut template = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            template.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => template.push('\n'),
+            Some('t') => template.push('\t'),
+            Some('\\') => template.push('\\'),
+            Some(other) => {
+                template.push('\\');
+                template.push(other);
+            }
+            None => template.push('\\'),
+        }
+    }
+    Ok(template)
+}
+
+/// Substitutes `{path}`, `{abs_path}`, `{lang}`, `{lines}`, `{size}`, `{index}` and `{note}`,
+/// leaving unknown variables as they are. The result always ends with a newline.
+pub fn render_file_template(template: &str, variables: &FileVariables) -> String {
+    let mut rendered = VARIABLE
+        .replace_all(template, |caps: &Captures| match &caps[1] {
+            "path" => variables.path.to_string(),
+            "abs_path" => variables.abs_path.to_string(),
+            "lang" => variables.lang.to_string(),
+            "lines" => variables.lines.to_string(),
+            "size" => variables.size.to_string(),
+            "index" => variables.index.to_string(),
+            "note" => variables.note.to_string(),
+            _ => caps[0].to_string(),
+        })
+        .into_owned();
+    if !rendered.ends_with('\n') {
+        rendered.push('\n');
+    }
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> FileVariables<'static> {
+        FileVariables {
+            path: "src/main.rs",
+            abs_path: "/repo/src/main.rs",
+            lang: "rust",
+            lines: 12,
+            size: 345,
+            index: 2,
+            note: "",
+        }
+    }
+
+    #[test]
+    fn test_parse_template_escapes() {
+        assert_eq!(
+            parse_template(r"<file>\n\t{path}\\n\q").unwrap(),
+            "<file>\n\t{path}\\n\\q"
+        );
+    }
+
+    #[test]
+    fn test_render_file_template() {
+        assert_eq!(
+            render_file_template(
+                "### {index}. {path} ({lang}, {lines} lines, {size} bytes) {unknown}",
+                &variables()
+            ),
+            "### 2. src/main.rs (rust, 12 lines, 345 bytes) {unknown}\n"
+        );
+    }
+
+    #[test]
+    fn test_render_file_template_keeps_trailing_newline() {
+        assert_eq!(
+            render_file_template("<file path=\"{abs_path}\">\n", &variables()),
+            "<file path=\"/repo/src/main.rs\">\n"
+        );
+    }
+}
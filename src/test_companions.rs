@@ -0,0 +1,283 @@
+use crate::import_graph::common_ancestor;
+use crate::language::Language;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PROJECT_MARKERS: [&str; 6] = [
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.py",
+    "go.mod",
+    ".git",
+];
+const JS_TEST_EXTENSIONS: [&str; 4] = ["ts", "tsx", "js", "jsx"];
+
+/// A test file pulled into the selection because it covers `source`
+pub struct Companion {
+    pub path: PathBuf,
+    pub source: PathBuf,
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+
+fn file_stem(path: &Path) -> &str {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
+}
+
+/// `dir` and its ancestors up to the enclosing project root, or just `dir` and its parent when
+/// no project root is found.
+fn project_ancestors(dir: &Path) -> Vec<&Path> {
+    let mut ancestors = Vec::new();
+    for ancestor in dir.ancestors() {
+        ancestors.push(ancestor);
+        if PROJECT_MARKERS
+            .iter()
+            .any(|marker| ancestor.join(marker).exists())
+        {
+            return ancestors;
+        }
+    }
+    dir.ancestors().take(2).collect()
+}
+
+/// Whether `path` already is a test, so looking for its own tests makes no sense.
+fn is_test_file(path: &Path) -> bool {
+    let name = file_name(path);
+    let stem = file_stem(path);
+    match Language::from_path(path) {
+        Some(Language::Go) => stem.ends_with("_test"),
+        Some(Language::Python) => stem.starts_with("test_") || stem.ends_with("_test"),
+        Some(Language::JavaScript | Language::TypeScript) => {
+            name.contains(".test.") || name.contains(".spec.")
+        }
+        Some(Language::Rust) => path
+            .parent()
+            .is_some_and(|parent| file_name(parent) == "tests"),
+        _ => false,
+    }
+}
+
+fn candidates(path: &Path) -> Vec<PathBuf> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let stem = file_stem(path);
+
+    match Language::from_path(path) {
+        Some(Language::Go) => vec![dir.join(format!("{}_test.go", stem))],
+        Some(Language::Python) => {
+            let mut candidates = vec![
+                dir.join(format!("test_{}.py", stem)),
+                dir.join(format!("{}_test.py", stem)),
+            ];
+            candidates.extend(
+                project_ancestors(dir)
+                    .into_iter()
+                    .map(|ancestor| ancestor.join("tests").join(format!("test_{}.py", stem))),
+            );
+            candidates
+        }
+        Some(Language::JavaScript | Language::TypeScript) => {
+            let own_extension = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .unwrap_or_default();
+            let mut extensions = vec![own_extension];
+            extensions.extend(JS_TEST_EXTENSIONS.iter().filter(|e| **e != own_extension));
+
+            let mut candidates = Vec::new();
+            for extension in extensions {
+                for kind in ["test", "spec"] {
+                    let name = format!("{}.{}.{}", stem, kind, extension);
+                    candidates.push(dir.join(&name));
+                    candidates.push(dir.join("__tests__").join(&name));
+                }
+                candidates.push(
+                    dir.join("__tests__")
+                        .join(format!("{}.{}", stem, extension)),
+                );
+            }
+            candidates
+        }
+        Some(Language::Rust) => {
+            // `mod.rs` is named after its directory
+            let module = if file_name(path) == "mod.rs" {
+                file_name(dir)
+            } else {
+                stem
+            };
+            let mut candidates = vec![dir.join(stem).join("tests.rs")];
+            candidates.extend(
+                project_ancestors(dir)
+                    .into_iter()
+                    .map(|ancestor| ancestor.join("tests").join(format!("{}.rs", module))),
+            );
+            candidates
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Finds the conventional test files for `files` (`foo_test.go`, `test_foo.py`,
+/// `foo.spec.ts`, `tests/foo.rs`, ...) that are not already part of the selection.
+pub fn find_test_companions(files: &[PathBuf]) -> Vec<Companion> {
+    let mut seen: HashSet<PathBuf> = files
+        .iter()
+        .map(|file| fs::canonicalize(file).unwrap_or_else(|_| file.clone()))
+        .collect();
+    let mut companions = Vec::new();
+
+    for source in files.iter().filter(|file| !is_test_file(file)) {
+        for candidate in candidates(source) {
+            if let Ok(canonical) = fs::canonicalize(&candidate)
+                && canonical.is_file()
+                && seen.insert(canonical)
+            {
+                companions.push(Companion {
+                    path: candidate,
+                    source: source.clone(),
+                });
+            }
+        }
+    }
+
+    companions
+}
+
+/// Canonicalizes a file named on the command line and returns it with the deepest directory
+/// containing both it and its tests, so that test paths such as `tests/foo.rs` stay readable.
+pub fn with_tests_base(file: &Path) -> Result<(PathBuf, PathBuf)> {
+    let file =
+        fs::canonicalize(file).with_context(|| format!("Failed to resolve {}", file.display()))?;
+    let mut selection = vec![file.clone()];
+    selection.extend(
+        find_test_companions(&selection)
+            .into_iter()
+            .map(|companion| companion.path),
+    );
+    Ok((file, common_ancestor(&selection)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::PackArgs;
+    use crate::directory_operations::join_blocks;
+    use crate::formatter::DefaultFormatter;
+    use crate::utils::pack_path;
+    use tempfile::TempDir;
+
+    fn companion_names(files: &[PathBuf], root: &Path) -> Vec<String> {
+        find_test_companions(files)
+            .iter()
+            .map(|companion| {
+                companion
+                    .path
+                    .strip_prefix(root)
+                    .unwrap()
+                    .display()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_go_and_python_companions() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("go.mod"), "").unwrap();
+        fs::create_dir(root.join("tests")).unwrap();
+        fs::write(root.join("server.go"), "").unwrap();
+        fs::write(root.join("server_test.go"), "").unwrap();
+        fs::write(root.join("parser.py"), "").unwrap();
+        fs::write(root.join("tests").join("test_parser.py"), "").unwrap();
+
+        let files = vec![root.join("server.go"), root.join("parser.py")];
+
+        assert_eq!(
+            companion_names(&files, root),
+            ["server_test.go", "tests/test_parser.py"]
+        );
+    }
+
+    #[test]
+    fn test_typescript_companions() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("__tests__")).unwrap();
+        fs::write(root.join("button.tsx"), "").unwrap();
+        fs::write(root.join("button.spec.tsx"), "").unwrap();
+        fs::write(root.join("__tests__").join("button.test.ts"), "").unwrap();
+
+        assert_eq!(
+            companion_names(&[root.join("button.tsx")], root),
+            ["button.spec.tsx", "__tests__/button.test.ts"]
+        );
+    }
+
+    #[test]
+    fn test_rust_integration_test_companion() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("Cargo.toml"), "").unwrap();
+        fs::create_dir_all(root.join("src").join("parser")).unwrap();
+        fs::create_dir(root.join("tests")).unwrap();
+        fs::write(root.join("src").join("parser.rs"), "").unwrap();
+        fs::write(root.join("src").join("parser").join("tests.rs"), "").unwrap();
+        fs::write(root.join("tests").join("parser.rs"), "").unwrap();
+
+        assert_eq!(
+            companion_names(&[root.join("src").join("parser.rs")], root),
+            ["src/parser/tests.rs", "tests/parser.rs"]
+        );
+    }
+
+    #[test]
+    fn test_companions_skip_selected_and_tests() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("util.go"), "").unwrap();
+        fs::write(root.join("util_test.go"), "").unwrap();
+        fs::write(root.join("util_test_test.go"), "").unwrap();
+
+        let files = vec![root.join("util.go"), root.join("util_test.go")];
+
+        assert!(find_test_companions(&files).is_empty());
+    }
+
+    #[test]
+    fn test_process_path_with_tests() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("Cargo.toml"), "").unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir(root.join("tests")).unwrap();
+        fs::write(root.join("src").join("lexer.rs"), "pub fn lex() {}\n").unwrap();
+        fs::write(
+            root.join("tests").join("lexer.rs"),
+            "#[test]\nfn lexes() {}\n",
+        )
+        .unwrap();
+
+        let cli = PackArgs {
+            with_tests: true,
+            ..Default::default()
+        };
+
+        let packed = pack_path(&cli, &root.join("src").join("lexer.rs")).unwrap();
+        let formatter = DefaultFormatter::from_args(&cli);
+        let content = join_blocks(&formatter, &packed.blocks(&cli, &formatter, &mut 0));
+
+        assert_eq!(
+            content,
+            "**src/lexer.rs:**\npub fn lex() {}\n\n----------\n**tests/lexer.rs:** [test for src/lexer.rs]\n#[test]\nfn lexes() {}\n\n"
+        );
+    }
+}
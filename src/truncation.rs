@@ -0,0 +1,134 @@
+/// How many lines to keep from the start and end of a long file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TruncateSpec {
+    pub head: usize,
+    pub tail: usize,
+}
+
+/// Parses a spec such as `head:40,tail:20`, `head:100` or `tail:50`.
+pub fn parse_truncate_spec(value: &str) -> Result<TruncateSpec, String> {
+    let mut spec = TruncateSpec::default();
+
+    for part in value
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (key, count) = part
+            .split_once(':')
+            .ok_or_else(|| format!("expected 'head:N' or 'tail:M', got '{}'", part))?;
+        let count: usize = count
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid line count in '{}'", part))?;
+        match key.trim() {
+            "head" => spec.head = count,
+            "tail" => spec.tail = count,
+            other => return Err(format!("unknown truncate key '{}'", other)),
+        }
+    }
+
+    if spec.head == 0 && spec.tail == 0 {
+        return Err("truncate spec must keep at least one head or tail line".to_string());
+    }
+
+    Ok(spec)
+}
+
+fn elision_marker(omitted: usize) -> String {
+    format!("... [{} lines omitted] ...\n", omitted)
+}
+
+/// Keeps the first `head` and last `tail` lines of `contents` when it has more than
+/// `threshold` lines, replacing the middle with an elision marker.
+pub fn truncate_head_tail(
+    contents: &str,
+    spec: TruncateSpec,
+    threshold: Option<usize>,
+) -> Option<String> {
+    let lines: Vec<&str> = contents.split_inclusive('\n').collect();
+    let kept = spec.head + spec.tail;
+    let threshold = threshold.unwrap_or(kept).max(kept);
+
+    if lines.len() <= threshold {
+        return None;
+    }
+
+    let omitted = lines.len() - kept;
+    let mut truncated = lines[..spec.head].concat();
+    truncated.push_str(&elision_marker(omitted));
+    truncated.push_str(&lines[lines.len() - spec.tail..].concat());
+
+    Some(truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered_lines(count: usize) -> String {
+        (1..=count).map(|i| format!("line{}\n", i)).collect()
+    }
+
+    #[test]
+    fn test_parse_truncate_spec() {
+        assert_eq!(
+            parse_truncate_spec("head:40,tail:20"),
+            Ok(TruncateSpec { head: 40, tail: 20 })
+        );
+        assert_eq!(
+            parse_truncate_spec("tail:5"),
+            Ok(TruncateSpec { head: 0, tail: 5 })
+        );
+    }
+
+    #[test]
+    fn test_parse_truncate_spec_invalid() {
+        assert!(parse_truncate_spec("").is_err());
+        assert!(parse_truncate_spec("head").is_err());
+        assert!(parse_truncate_spec("middle:3").is_err());
+        assert!(parse_truncate_spec("head:x").is_err());
+        assert!(parse_truncate_spec("head:0,tail:0").is_err());
+    }
+
+    #[test]
+    fn test_truncate_head_tail() {
+        let contents = numbered_lines(10);
+        let spec = TruncateSpec { head: 2, tail: 3 };
+
+        let truncated = truncate_head_tail(&contents, spec, None).unwrap();
+
+        assert_eq!(
+            truncated,
+            "line1\nline2\n... [5 lines omitted] ...\nline8\nline9\nline10\n"
+        );
+    }
+
+    #[test]
+    fn test_truncate_head_tail_short_file_untouched() {
+        let contents = numbered_lines(5);
+        let spec = TruncateSpec { head: 2, tail: 3 };
+
+        assert_eq!(truncate_head_tail(&contents, spec, None), None);
+    }
+
+    #[test]
+    fn test_truncate_head_tail_threshold() {
+        let contents = numbered_lines(10);
+        let spec = TruncateSpec { head: 1, tail: 1 };
+
+        assert_eq!(truncate_head_tail(&contents, spec, Some(20)), None);
+        assert!(truncate_head_tail(&contents, spec, Some(9)).is_some());
+    }
+
+    #[test]
+    fn test_truncate_head_only() {
+        let contents = numbered_lines(4);
+        let spec = TruncateSpec { head: 1, tail: 0 };
+
+        assert_eq!(
+            truncate_head_tail(&contents, spec, None).unwrap(),
+            "line1\n... [3 lines omitted] ...\n"
+        );
+    }
+}
@@ -1,72 +1,340 @@
-use crate::cli::Cli;
-use crate::directory_operations::process_directory;
-use crate::file_operations::process_file;
+use crate::cli::PackArgs;
+use crate::directory_operations::{
+    FileBlock, pack_files, render_blocks, report_failures, walk_directory,
+};
+use crate::file_operations::redacted_display_path;
+use crate::formatter::Formatter;
+use crate::import_graph::{
+    collect_import_graph, common_ancestor, follows_imports, format_external_imports,
+};
+use crate::language::Language;
+use crate::line_endings::LineEndings;
+use crate::packer::PackedFile;
+use crate::rust_modules::module_tree_selection;
+use crate::selection::SkippedFile;
+use crate::stats::TextStats;
+use crate::test_companions::with_tests_base;
 use anyhow::Result;
-use std::path::Path;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 pub fn separator() -> String {
     "-".repeat(10)
 }
 
-pub fn process_path(cli: &Cli, path: &Path) -> Result<(String, usize, usize, usize)> {
-    if path.is_dir() {
-        process_directory(cli, path)
+/// The line printed between files, from `--separator` when given.
+pub fn separator_line(cli: &PackArgs) -> String {
+    match cli.separator.as_deref() {
+        Some("") => String::new(),
+        Some(separator) if separator.ends_with('\n') => separator.to_string(),
+        Some(separator) => format!("{}\n", separator),
+        None => format!("{}\n", separator()),
+    }
+}
+
+/// `path` with symlinks and `..` resolved, or as given when it cannot be resolved
+pub fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The files one command-line path expands to
+struct PathSelection {
+    files: Vec<PathBuf>,
+    /// Directory the display paths are relative to; empty for a file named on its own
+    base: PathBuf,
+    /// The file named on the command line, canonicalized, which is never refused as sensitive
+    explicit: Option<PathBuf>,
+    /// Third-party modules found by `--follow-imports`
+    external: BTreeSet<String>,
+    /// `--show-symlinks` entries for the symlinks that were not followed
+    symlinks: Vec<PackedFile>,
+    /// Directory entries that could not be read
+    failures: Vec<PackedFile>,
+    /// Symlinks that were not followed, with the reason
+    skipped: Vec<SkippedFile>,
+    /// Notices about the symlinks that were not followed
+    diagnostics: Vec<String>,
+}
+
+/// An entry for `path` that could not be read or resolved, shown relative to `base`
+fn failure(cli: &PackArgs, path: &Path, base: &Path, error: String) -> PackedFile {
+    PackedFile {
+        display_path: redacted_display_path(cli, path, base)
+            .unwrap_or_else(|_| path.display().to_string()),
+        path: path.to_path_buf(),
+        errors: vec![error],
+        ..Default::default()
+    }
+}
+
+/// Expands one command-line path into the files to process, following modules, imports or
+/// tests when asked to.
+fn select_path(cli: &PackArgs, path: &Path) -> Result<PathSelection> {
+    let explicit = path.is_file().then(|| canonical_path(path));
+    let selection = |files, base| PathSelection {
+        files,
+        base,
+        explicit: explicit.clone(),
+        external: BTreeSet::new(),
+        symlinks: Vec::new(),
+        failures: Vec::new(),
+        skipped: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+
+    let selection = if path.is_dir() {
+        let walk = walk_directory(cli, path);
+        PathSelection {
+            symlinks: walk.symlinks,
+            failures: walk.failures,
+            skipped: walk.skipped,
+            diagnostics: walk.diagnostics,
+            ..selection(walk.files, path.to_path_buf())
+        }
     } else if path.is_file() {
-        process_file(cli, path)
+        if cli.follow_mods && Language::from_path(path) == Some(Language::Rust) {
+            let (tree, dir) = module_tree_selection(path)?;
+            PathSelection {
+                failures: tree
+                    .missing
+                    .into_iter()
+                    .map(|(expected, error)| failure(cli, &expected, &dir, error))
+                    .collect(),
+                ..selection(tree.files, dir)
+            }
+        } else if follows_imports(cli, path) {
+            let graph = collect_import_graph(path, cli.import_depth)?;
+            PathSelection {
+                external: graph.external,
+                ..selection(graph.files.clone(), common_ancestor(&graph.files))
+            }
+        } else if cli.with_tests {
+            let (file, base) = with_tests_base(path)?;
+            selection(vec![file], base)
+        } else {
+            selection(vec![path.to_path_buf()], PathBuf::new())
+        }
     } else {
-        if cli.include_errors {
-            eprintln!(
-                "ERROR: Path '{}' is neither a file nor a directory",
-                path.display()
-            );
+        let error = format!(
+            "Path '{}' is neither a file nor a directory",
+            path.display()
+        );
+        PathSelection {
+            failures: vec![failure(cli, path, Path::new(""), error)],
+            ..selection(Vec::new(), PathBuf::new())
         }
-        Ok((String::new(), 0, 0, 0))
+    };
+
+    if cli.errors_to_stderr() {
+        report_failures(&selection.failures);
     }
+    Ok(selection)
 }
 
-pub fn output_information(cli: &Cli) -> Result<String> {
-    let mut total_chars = 0;
-    let mut total_words = 0;
-    let mut total_non_empty_lines = 0;
-    let mut paths_str = String::new();
-    let mut buf = String::new();
+/// The files gathered for one command-line path
+pub struct PackedPath {
+    pub files: Vec<PackedFile>,
+    /// Third-party modules found by `--follow-imports`
+    pub external: BTreeSet<String>,
+    /// Files left out of the selection or omitted for their size, with the reason
+    pub skipped: Vec<SkippedFile>,
+    /// Notices about files and symlinks that were skipped, when errors go to stderr
+    pub diagnostics: Vec<String>,
+}
+
+impl PackedPath {
+    /// Prints the notices about the path and each of its files to stderr.
+    pub fn report_diagnostics(&self) {
+        let files = self.files.iter().flat_map(|file| &file.diagnostics);
+        for diagnostic in self.diagnostics.iter().chain(files) {
+            eprintln!("{}", diagnostic);
+        }
+    }
+
+    pub fn stats(&self) -> TextStats {
+        self.files.iter().map(|file| file.stats).sum()
+    }
+
+    /// Renders the files into blocks numbered on from `index`, ending with a summary of
+    /// external imports when `--follow-imports` found any.
+    pub fn blocks(
+        &self,
+        cli: &PackArgs,
+        formatter: &dyn Formatter,
+        index: &mut usize,
+    ) -> Vec<FileBlock> {
+        let mut blocks = render_blocks(cli, formatter, &self.files, index);
+        if !cli.output_information && !self.external.is_empty() {
+            blocks.push(FileBlock {
+                display_path: "External imports".to_string(),
+                content: format_external_imports(&self.external),
+            });
+        }
+        blocks
+    }
+}
+
+/// Packs the files one command-line path expands to.
+pub fn pack_path(cli: &PackArgs, path: &Path) -> Result<PackedPath> {
+    let selection = select_path(cli, path)?;
+    let mut diagnostics = selection.diagnostics;
+    let mut skipped = selection.skipped;
+    let mut files = pack_files(
+        cli,
+        &selection.files,
+        &selection.base,
+        selection.explicit.as_deref(),
+        &mut diagnostics,
+        &mut skipped,
+    );
+    files.extend(selection.symlinks);
+    files.extend(selection.failures);
+    Ok(PackedPath {
+        files,
+        external: selection.external,
+        skipped,
+        diagnostics,
+    })
+}
+
+fn has_size_limits(cli: &PackArgs) -> bool {
+    cli.truncate.is_some() || cli.max_file_size.is_some() || cli.max_file_lines.is_some()
+}
+
+/// Totals over every path
+pub struct Totals {
+    /// The paths that could be processed
+    paths: Vec<String>,
+    /// Number of files packed, whether output in full or omitted
+    pub files: usize,
+    /// Errors of the files and directories that could not be read
+    pub failures: Vec<String>,
+    stats: TextStats,
+    /// Counts before the size limits shortened any file
+    original_stats: TextStats,
+    /// Files that had `\r\n` line endings on disk
+    crlf_files: usize,
+    /// Files that had more than one kind of line ending on disk
+    mixed_eol_files: usize,
+    /// Matches replaced by each `--redact-regex` rule, in the order given
+    rule_hits: Vec<usize>,
+}
+
+pub fn gather_totals(cli: &PackArgs) -> Totals {
+    let mut totals = Totals {
+        paths: Vec::new(),
+        files: 0,
+        failures: Vec::new(),
+        stats: TextStats::default(),
+        original_stats: TextStats::default(),
+        crlf_files: 0,
+        mixed_eol_files: 0,
+        rule_hits: vec![0; cli.redact_rules.len()],
+    };
 
     for path in &cli.paths {
-        match process_path(cli, path) {
-            Ok((_, char_count, word_count, non_empty_line_count)) => {
-                total_chars += char_count;
-                total_words += word_count;
-                total_non_empty_lines += non_empty_line_count;
-                if !paths_str.is_empty() {
-                    paths_str.push(' ');
+        match pack_path(cli, path) {
+            Ok(packed) => {
+                packed.report_diagnostics();
+                totals.stats += packed.stats();
+                for file in &packed.files {
+                    if file.errors.is_empty() {
+                        totals.files += 1;
+                    } else {
+                        totals.failures.extend(file.errors.iter().cloned());
+                    }
+                    totals.original_stats += file.original_stats;
+                    match file.line_endings {
+                        LineEndings::Crlf => totals.crlf_files += 1,
+                        LineEndings::Mixed => totals.mixed_eol_files += 1,
+                        LineEndings::Lf => {}
+                    }
+                    for (total, hits) in totals.rule_hits.iter_mut().zip(&file.rule_hits) {
+                        *total += hits;
+                    }
                 }
-                paths_str.push_str(&path.display().to_string());
+                totals.paths.push(path.display().to_string());
             }
             Err(e) => {
-                if cli.include_errors {
+                if cli.errors_to_stderr() {
                     eprintln!("ERROR gathering info for path {}: {}", path.display(), e);
                 }
+                totals.failures.push(format!("{:#}", e));
             }
         }
     }
 
-    buf.push_str(&format!("Paths: {}\n", paths_str));
-    buf.push_str(&format!("Total Characters: {}\n", total_chars));
-    buf.push_str(&format!("Total Words: {}\n", total_words));
+    totals
+}
+
+/// The `-o` report of the `totals` gathered for `cli`.
+pub fn output_information(cli: &PackArgs, totals: &Totals) -> String {
+    let mut buf = String::new();
+
+    buf.push_str(&format!("Paths: {}\n", totals.paths.join(" ")));
+    buf.push_str(&format!("Total Characters: {}\n", totals.stats.chars));
+    buf.push_str(&format!("Total Words: {}\n", totals.stats.words));
+    buf.push_str(&format!("Total Non-Empty Lines: {}\n", totals.stats.lines));
+    buf.push_str(&format!(
+        "Files With CRLF Line Endings: {}\n",
+        totals.crlf_files
+    ));
     buf.push_str(&format!(
-        "Total Non-Empty Lines: {}\n",
-        total_non_empty_lines
+        "Files With Mixed Line Endings: {}\n",
+        totals.mixed_eol_files
     ));
 
-    Ok(buf)
+    for (rule, hits) in cli.redact_rules.iter().zip(&totals.rule_hits) {
+        buf.push_str(&format!(
+            "Redactions for '{}': {}\n",
+            rule.pattern.as_str(),
+            hits
+        ));
+    }
+
+    if has_size_limits(cli) {
+        let original = totals.original_stats;
+        buf.push_str(&format!("Original Characters: {}\n", original.chars));
+        buf.push_str(&format!("Original Words: {}\n", original.words));
+        buf.push_str(&format!("Original Non-Empty Lines: {}\n", original.lines));
+    }
+
+    buf
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::directory_operations::join_blocks;
+    use crate::formatter::DefaultFormatter;
+    use crate::line_endings::EolMode;
+    use crate::redaction::parse_redact_rule;
+    use crate::truncation::TruncateSpec;
     use std::fs;
     use tempfile::TempDir;
 
+    fn information(cli: &PackArgs) -> String {
+        output_information(cli, &gather_totals(cli))
+    }
+
+    #[test]
+    fn test_separator_line() {
+        assert_eq!(separator_line(&PackArgs::default()), "----------\n");
+
+        let custom = PackArgs {
+            separator: Some("=====".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(separator_line(&custom), "=====\n");
+
+        let empty = PackArgs {
+            separator: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(separator_line(&empty), "");
+    }
+
     #[test]
     fn test_separator() {
         let sep = separator();
@@ -75,47 +343,59 @@ mod tests {
     }
 
     #[test]
-    fn test_process_path_file() {
+    fn test_pack_path_file() {
         let temp_dir = TempDir::new().unwrap();
         let file_path = temp_dir.path().join("test.txt");
         fs::write(&file_path, "Hello World\n").unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![],
             max_depth: 10,
-            include_errors: false,
-            output_information: false,
-            copy: false,
+            ..Default::default()
         };
 
-        let (content, chars, words, lines) = process_path(&cli, &file_path).unwrap();
-
-        assert!(content.contains("**test.txt:**"));
-        assert!(content.contains("Hello World"));
-        assert_eq!(chars, 12);
-        assert_eq!(words, 2);
-        assert_eq!(lines, 1);
+        let packed = pack_path(&cli, &file_path).unwrap();
+        let formatter = DefaultFormatter::from_args(&cli);
+
+        assert_eq!(packed.files.len(), 1);
+        assert_eq!(packed.files[0].display_path, "test.txt");
+        assert_eq!(packed.files[0].content, "Hello World\n");
+        assert_eq!(
+            packed.stats(),
+            TextStats {
+                chars: 12,
+                words: 2,
+                lines: 1
+            }
+        );
+        assert_eq!(
+            join_blocks(&formatter, &packed.blocks(&cli, &formatter, &mut 0)),
+            "**test.txt:**\nHello World\n\n"
+        );
     }
 
     #[test]
-    fn test_process_path_directory() {
+    fn test_pack_path_directory() {
         let temp_dir = TempDir::new().unwrap();
         fs::write(temp_dir.path().join("file.txt"), "Content\n").unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![],
             max_depth: 10,
-            include_errors: false,
-            output_information: false,
-            copy: false,
+            ..Default::default()
         };
 
-        let (content, chars, words, lines) = process_path(&cli, temp_dir.path()).unwrap();
+        let packed = pack_path(&cli, temp_dir.path()).unwrap();
 
-        assert!(content.contains("**file.txt:**"));
-        assert_eq!(chars, 8);
-        assert_eq!(words, 1);
-        assert_eq!(lines, 1);
+        assert_eq!(packed.files[0].display_path, "file.txt");
+        assert_eq!(
+            packed.stats(),
+            TextStats {
+                chars: 8,
+                words: 1,
+                lines: 1
+            }
+        );
     }
 
     #[test]
@@ -124,15 +404,14 @@ mod tests {
         let file_path = temp_dir.path().join("test.txt");
         fs::write(&file_path, "Hello\nWorld\n").unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![file_path.clone()],
             max_depth: 10,
-            include_errors: false,
             output_information: true,
-            copy: false,
+            ..Default::default()
         };
 
-        let info = output_information(&cli).unwrap();
+        let info = information(&cli);
 
         assert!(info.contains("Paths:"));
         assert!(info.contains("test.txt"));
@@ -141,6 +420,29 @@ mod tests {
         assert!(info.contains("Total Non-Empty Lines: 2"));
     }
 
+    #[test]
+    fn test_output_information_line_endings() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("unix.txt"), "a\nb\n").unwrap();
+        fs::write(temp_dir.path().join("windows.txt"), "a\r\nb\r\n").unwrap();
+        fs::write(temp_dir.path().join("mixed.txt"), "a\r\nb\n").unwrap();
+
+        let cli = PackArgs {
+            paths: vec![temp_dir.path().to_path_buf()],
+            max_depth: 10,
+            output_information: true,
+            eol: EolMode::Lf,
+            ..Default::default()
+        };
+
+        let info = information(&cli);
+
+        // Normalized to LF, so the counts match across the three files
+        assert!(info.contains("Total Characters: 12"));
+        assert!(info.contains("Files With CRLF Line Endings: 1\n"));
+        assert!(info.contains("Files With Mixed Line Endings: 1\n"));
+    }
+
     #[test]
     fn test_output_information_multiple_paths() {
         let temp_dir = TempDir::new().unwrap();
@@ -149,15 +451,14 @@ mod tests {
         fs::write(&file1, "One\n").unwrap();
         fs::write(&file2, "Two Three\n").unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![file1, file2],
             max_depth: 10,
-            include_errors: false,
             output_information: true,
-            copy: false,
+            ..Default::default()
         };
 
-        let info = output_information(&cli).unwrap();
+        let info = information(&cli);
 
         assert!(info.contains("Total Characters: 14")); // "One\n" + "Two Three\n"
         assert!(info.contains("Total Words: 3"));
@@ -170,36 +471,114 @@ mod tests {
         fs::write(temp_dir.path().join("file1.txt"), "A B\n").unwrap();
         fs::write(temp_dir.path().join("file2.txt"), "C D E\n").unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![temp_dir.path().to_path_buf()],
             max_depth: 10,
-            include_errors: false,
             output_information: true,
-            copy: false,
+            ..Default::default()
         };
 
-        let info = output_information(&cli).unwrap();
+        let info = information(&cli);
 
         assert!(info.contains("Total Characters: 10")); // "A B\n" + "C D E\n"
         assert!(info.contains("Total Words: 5"));
         assert!(info.contains("Total Non-Empty Lines: 2"));
     }
 
+    #[test]
+    fn test_output_information_reports_original_when_truncated() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "a\nb\nc\nd\ne\nf\n").unwrap();
+
+        let cli = PackArgs {
+            paths: vec![file_path],
+            max_depth: 10,
+            output_information: true,
+            truncate: Some(TruncateSpec { head: 1, tail: 1 }),
+            ..Default::default()
+        };
+
+        let info = information(&cli);
+
+        assert!(info.contains("Total Non-Empty Lines: 3"));
+        assert!(info.contains("Original Characters: 12"));
+        assert!(info.contains("Original Words: 6"));
+        assert!(info.contains("Original Non-Empty Lines: 6"));
+    }
+
+    #[test]
+    fn test_output_information_original_counts_omitted_files() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("big.txt"), "a\nb\nc\n").unwrap();
+        fs::write(temp_dir.path().join("small.txt"), "d\n").unwrap();
+
+        let cli = PackArgs {
+            paths: vec![temp_dir.path().to_path_buf()],
+            max_depth: 10,
+            output_information: true,
+            max_file_lines: Some(2),
+            ..Default::default()
+        };
+
+        let info = information(&cli);
+
+        assert!(info.contains("Total Characters: 2\n"));
+        assert!(info.contains("Original Characters: 8\n"));
+        assert!(info.contains("Original Non-Empty Lines: 4\n"));
+    }
+
+    #[test]
+    fn test_output_information_no_original_without_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "a\n").unwrap();
+
+        let cli = PackArgs {
+            paths: vec![file_path],
+            max_depth: 10,
+            output_information: true,
+            ..Default::default()
+        };
+
+        let info = information(&cli);
+
+        assert!(!info.contains("Original"));
+    }
+
+    #[test]
+    fn test_output_information_redaction_hits() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "host1.corp host2.corp\n").unwrap();
+
+        let cli = PackArgs {
+            paths: vec![file_path],
+            max_depth: 10,
+            output_information: true,
+            redact_rules: vec![parse_redact_rule(r"host\d\.corp=>[HOST]").unwrap()],
+            ..Default::default()
+        };
+
+        let info = information(&cli);
+
+        assert!(info.contains("Redactions for 'host\\d\\.corp': 2"));
+    }
+
     #[test]
     fn test_output_information_empty_lines() {
         let temp_dir = TempDir::new().unwrap();
         let file_path = temp_dir.path().join("test.txt");
         fs::write(&file_path, "Line1\n\n\nLine2\n").unwrap();
 
-        let cli = Cli {
+        let cli = PackArgs {
             paths: vec![file_path],
             max_depth: 10,
-            include_errors: false,
             output_information: true,
-            copy: false,
+            ..Default::default()
         };
 
-        let info = output_information(&cli).unwrap();
+        let info = information(&cli);
 
         assert!(info.contains("Total Non-Empty Lines: 2")); // Only Line1 and Line2
     }